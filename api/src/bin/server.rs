@@ -1,5 +1,6 @@
 use std::{
     net::{IpAddr, SocketAddr},
+    path::PathBuf,
     time::Duration,
 };
 
@@ -19,6 +20,9 @@ pub struct Config {
     pub port: u16,
     #[clap(long, env)]
     pub database_url: String,
+    /// Directory the uploaded files are stored in
+    #[clap(long, default_value = "storage", env)]
+    pub storage_dir: PathBuf,
 }
 
 impl Config {
@@ -91,7 +95,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
     db_migration(&db_connection).await?;
 
     // build our application with a route
-    let app = api::app(db_connection);
+    let app = api::app(
+        db_connection,
+        api::LogMailer,
+        api::LocalStorage::new(args.storage_dir),
+    );
 
     // run it
     let addr = SocketAddr::from((args.host, args.port));
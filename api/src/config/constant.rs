@@ -1,3 +1,50 @@
 pub(crate) const BEARER: &str = "Bearer";
 pub(crate) const REFRESH_TOKEN_TIMEOUT: u64 = 30 * 24 * 60 * 60;
 pub(crate) const ACCESS_TOKEN_TIMEOUT: u64 = 5 * 60 * 60;
+pub(crate) const REAUTHENTICATION_TIMEOUT: u64 = 5 * 60;
+pub(crate) const PASSWORD_RESET_TOKEN_TIMEOUT: u64 = 30 * 60;
+pub(crate) const PASSWORD_RESET_RESEND_COOLDOWN: u64 = 60;
+pub(crate) const EMAIL_VERIFICATION_TOKEN_TIMEOUT: u64 = 24 * 60 * 60;
+pub(crate) const EMAIL_VERIFICATION_RESEND_COOLDOWN: u64 = 60;
+pub(crate) const MAGIC_LINK_TOKEN_TIMEOUT: u64 = 15 * 60;
+pub(crate) const MAGIC_LINK_RESEND_COOLDOWN: u64 = 60;
+pub(crate) const MFA_TOKEN_TIMEOUT: u64 = 5 * 60;
+/// Second factor guesses per `mfa_token`, whatever the method
+pub(crate) const MFA_LOGIN_ATTEMPTS: i32 = 5;
+pub(crate) const TOTP_ISSUER: &str = "playground";
+pub(crate) const RECOVERY_CODE_COUNT: usize = 10;
+pub(crate) const PASSKEY_CEREMONY_TIMEOUT: u64 = 5 * 60;
+/// Ongoing ceremonies of each kind kept in memory
+pub(crate) const PASSKEY_CEREMONY_CAPACITY: usize = 10_000;
+pub(crate) const PASSKEY_CEREMONY_RETRY_AFTER: u64 = 5;
+pub(crate) const WEBAUTHN_RP_NAME: &str = "playground";
+pub(crate) const MFA_EMAIL_CODE_TIMEOUT: u64 = 10 * 60;
+pub(crate) const MFA_EMAIL_CODE_ATTEMPTS: i32 = 5;
+pub(crate) const MFA_EMAIL_CODE_RESEND_COOLDOWN: u64 = 60;
+pub(crate) const TRUSTED_DEVICE_TIMEOUT: u64 = 30 * 24 * 60 * 60;
+pub(crate) const LOGIN_FAILURE_WINDOW: u64 = 60 * 60;
+/// How often failures outside the window are forgotten
+pub(crate) const LOGIN_THROTTLE_PRUNE_INTERVAL: u64 = 60;
+pub(crate) const LOGIN_BACKOFF_BASE: u64 = 1;
+pub(crate) const ACCOUNT_FREE_LOGIN_ATTEMPTS: u32 = 3;
+pub(crate) const ACCOUNT_LOGIN_BACKOFF_MAX: u64 = 60;
+pub(crate) const ACCOUNT_LOCKOUT_THRESHOLD: u32 = 10;
+pub(crate) const ACCOUNT_LOCKOUT_DURATION: u64 = 15 * 60;
+pub(crate) const IP_FREE_LOGIN_ATTEMPTS: u32 = 20;
+pub(crate) const IP_LOGIN_BACKOFF_MAX: u64 = 15 * 60;
+pub(crate) const PASSWORD_HASHING_RETRY_AFTER: u64 = 1;
+pub(crate) const DATABASE_RETRY_AFTER: u64 = 5;
+pub(crate) const ACCOUNT_PURGE_INTERVAL: u64 = 60 * 60;
+pub(crate) const USER_EXPORT_TIMEOUT: u64 = 24 * 60 * 60;
+pub(crate) const USER_EXPORT_INLINE_WAIT: u64 = 2;
+pub(crate) const USER_EXPORT_RETRY_AFTER: u64 = 5;
+pub(crate) const AVATAR_MAX_SIZE: usize = 5 * 1024 * 1024;
+pub(crate) const AVATAR_MAX_DIMENSION: u32 = 4096;
+/// Bytes the decoder may allocate, a 4096x4096 RGBA image with some slack
+pub(crate) const AVATAR_MAX_ALLOC: u64 = 128 * 1024 * 1024;
+/// Uploads decoded at once, each may allocate up to `AVATAR_MAX_ALLOC`
+pub(crate) const AVATAR_MAX_DECODES: usize = 4;
+pub(crate) const AVATAR_DECODE_RETRY_AFTER: u64 = 5;
+/// The first one is the `avatar_url` of the user
+pub(crate) const AVATAR_SIZES: [u32; 2] = [256, 64];
+pub(crate) const AVATAR_CACHE_MAX_AGE: u64 = 365 * 24 * 60 * 60;
@@ -7,8 +7,77 @@ lazy_static! {
         env::var("JWT_REFRESH_SECRET").expect("JWT_REFRESH_SECRET must be set");
     static ref STRING_JWT_ACCESS_SECRET: String =
         env::var("JWT_ACCESS_SECRET").expect("JWT_ACCESS_SECRET must be set");
+    static ref STRING_JWT_MAGIC_LINK_SECRET: String =
+        env::var("JWT_MAGIC_LINK_SECRET")
+            .expect("JWT_MAGIC_LINK_SECRET must be set");
+    static ref STRING_JWT_MFA_SECRET: String =
+        env::var("JWT_MFA_SECRET").expect("JWT_MFA_SECRET must be set");
+    static ref STRING_JWT_TRUSTED_DEVICE_SECRET: String =
+        env::var("JWT_TRUSTED_DEVICE_SECRET")
+            .expect("JWT_TRUSTED_DEVICE_SECRET must be set");
     pub(crate) static ref JWT_REFRESH_SECRET: &'static [u8] =
         STRING_JWT_REFRESH_SECRET.as_bytes();
     pub(crate) static ref JWT_ACCESS_SECRET: &'static [u8] =
         STRING_JWT_ACCESS_SECRET.as_bytes();
+    pub(crate) static ref JWT_MAGIC_LINK_SECRET: &'static [u8] =
+        STRING_JWT_MAGIC_LINK_SECRET.as_bytes();
+    pub(crate) static ref JWT_MFA_SECRET: &'static [u8] =
+        STRING_JWT_MFA_SECRET.as_bytes();
+    pub(crate) static ref JWT_TRUSTED_DEVICE_SECRET: &'static [u8] =
+        STRING_JWT_TRUSTED_DEVICE_SECRET.as_bytes();
+    pub(crate) static ref FRONTEND_URL: String = env::var("FRONTEND_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    /// Where the api itself is reachable, for the urls of uploaded files
+    pub(crate) static ref API_URL: String = env::var("API_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    pub(crate) static ref WEBAUTHN_RP_ID: String =
+        env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string());
+    pub(crate) static ref WEBAUTHN_RP_ORIGIN: String =
+        env::var("WEBAUTHN_RP_ORIGIN").unwrap_or_else(|_| FRONTEND_URL.clone());
+    pub(crate) static ref EMAIL_VERIFICATION_POLICY: EmailVerificationPolicy =
+        match env::var("EMAIL_VERIFICATION_POLICY").as_deref() {
+            Ok("required") => EmailVerificationPolicy::Required,
+            Ok("optional") | Err(_) => EmailVerificationPolicy::Optional,
+            Ok(policy) => {
+                panic!("EMAIL_VERIFICATION_POLICY {policy:?} is not supported")
+            }
+        };
+    /// Defaults to one thread per core
+    pub(crate) static ref PASSWORD_HASHING_THREADS: Option<usize> =
+        env::var("PASSWORD_HASHING_THREADS").ok().map(|threads| {
+            threads
+                .parse()
+                .expect("PASSWORD_HASHING_THREADS must be a number")
+        });
+    /// Hashing jobs that may be queued or running before requests are
+    /// turned away
+    pub(crate) static ref PASSWORD_HASHING_QUEUE_SIZE: usize =
+        env::var("PASSWORD_HASHING_QUEUE_SIZE").map_or(64, |size| {
+            size.parse()
+                .expect("PASSWORD_HASHING_QUEUE_SIZE must be a number")
+        });
+    /// Static bearer token Prometheus scrapes `/metrics` with, the endpoint
+    /// rejects every request without one
+    pub(crate) static ref METRICS_TOKEN: Option<String> =
+        env::var("METRICS_TOKEN").ok();
+    /// Reverse proxies in front of the api that append the client to
+    /// `X-Forwarded-For`, none means the peer is the client
+    pub(crate) static ref TRUSTED_PROXIES: usize =
+        env::var("TRUSTED_PROXIES").map_or(0, |proxies| {
+            proxies.parse().expect("TRUSTED_PROXIES must be a number")
+        });
+    /// Days a deleted account can still be restored by logging back in
+    pub(crate) static ref ACCOUNT_DELETION_GRACE_DAYS: u64 =
+        env::var("ACCOUNT_DELETION_GRACE_DAYS").map_or(30, |days| {
+            days.parse()
+                .expect("ACCOUNT_DELETION_GRACE_DAYS must be a number")
+        });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EmailVerificationPolicy {
+    /// Unverified users can log in
+    Optional,
+    /// Unverified users can't log in
+    Required,
 }
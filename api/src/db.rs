@@ -1,3 +1,8 @@
 pub(crate) mod error;
+mod one_time_token;
+mod passkey;
+mod recovery_code;
 mod refresh_token;
+mod role;
+mod trusted_device;
 mod user;
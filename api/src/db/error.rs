@@ -1,7 +1,13 @@
 pub(crate) type DbResult<T> = std::result::Result<T, DbError>;
 
+use sea_orm::{DbErr, RuntimeErr};
+use sqlx::{error::DatabaseError, postgres::PgDatabaseError};
 use thiserror::Error as ErrorTrait;
 
+// https://www.postgresql.org/docs/current/errcodes-appendix.html
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
 #[derive(Debug, ErrorTrait)]
 pub(crate) enum DbError {
     #[error("no result")]
@@ -9,13 +15,118 @@ pub(crate) enum DbError {
 
     #[error("missing relation")]
     MissingRelation,
+
+    #[error("invalid data")]
+    InvalidData,
+
+    /// Holds the column when it can be derived from the constraint
+    #[error("unique constraint violated on {0:?}")]
+    UniqueViolation(Option<String>),
+
+    #[error("foreign key constraint {0:?} violated")]
+    ForeignKeyViolation(Option<String>),
+
+    #[error("database unavailable: {0}")]
+    Unavailable(DbErr),
+
+    #[error(transparent)]
+    Other(DbErr),
 }
 
-impl From<sea_orm::DbErr> for DbError {
-    fn from(err: sea_orm::DbErr) -> Self {
-        {
-            dbg!(err);
-            todo!();
+impl From<DbErr> for DbError {
+    fn from(err: DbErr) -> Self {
+        match err {
+            DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated => {
+                Self::NoResult
+            }
+            DbErr::ConnectionAcquire | DbErr::Conn(_) => Self::Unavailable(err),
+            DbErr::Exec(RuntimeErr::SqlxError(ref sqlx_err))
+            | DbErr::Query(RuntimeErr::SqlxError(ref sqlx_err)) => {
+                match sqlx_err {
+                    sqlx::Error::Database(database_err) => {
+                        from_database_error(database_err.as_ref())
+                            .unwrap_or(Self::Other(err))
+                    }
+                    sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::WorkerCrashed => Self::Unavailable(err),
+                    _ => Self::Other(err),
+                }
+            }
+            _ => Self::Other(err),
+        }
+    }
+}
+
+fn from_database_error(err: &dyn DatabaseError) -> Option<DbError> {
+    match err.code()?.as_ref() {
+        UNIQUE_VIOLATION => {
+            let table = err
+                .try_downcast_ref::<PgDatabaseError>()
+                .and_then(PgDatabaseError::table);
+            let column = err
+                .constraint()
+                .and_then(|constraint| constraint_column(constraint, table?));
+
+            Some(DbError::UniqueViolation(column))
         }
+        FOREIGN_KEY_VIOLATION => Some(DbError::ForeignKeyViolation(
+            err.constraint().map(ToOwned::to_owned),
+        )),
+        _ => None,
+    }
+}
+
+/// The migrations name unique indexes `idx-<table>-<column>`, postgres names
+/// unique column constraints `<table>_<column>_key`
+fn constraint_column(constraint: &str, table: &str) -> Option<String> {
+    let column = match constraint.strip_prefix("idx-") {
+        Some(index) => index.strip_prefix(table)?.strip_prefix('-')?,
+        None => constraint
+            .strip_suffix("_key")?
+            .strip_prefix(table)?
+            .strip_prefix('_')?,
+    };
+
+    Some(column.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_from_postgres_constraint() {
+        assert_eq!(
+            constraint_column("user_email_key", "user").as_deref(),
+            Some("email")
+        );
+        assert_eq!(
+            constraint_column("user_role_user_uuid_key", "user_role")
+                .as_deref(),
+            Some("user_uuid")
+        );
+    }
+
+    #[test]
+    fn column_from_migration_index() {
+        assert_eq!(
+            constraint_column("idx-user-displayname", "user").as_deref(),
+            Some("displayname")
+        );
+        assert_eq!(
+            constraint_column("idx-user_role-user_uuid", "user_role")
+                .as_deref(),
+            Some("user_uuid")
+        );
+    }
+
+    #[test]
+    fn unrelated_constraint_has_no_column() {
+        assert_eq!(constraint_column("user_pkey", "user"), None);
+        assert_eq!(constraint_column("user_role_pkey", "user"), None);
+        assert_eq!(constraint_column("passkey_name_key", "user"), None);
     }
 }
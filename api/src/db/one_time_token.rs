@@ -0,0 +1,213 @@
+use entity::one_time_token::{
+    self as entity_one_time_token, ActiveModel as ActiveModelOneTimeToken,
+    Entity as EntityOneTimeToken, Model as ModelOneTimeToken,
+};
+use sea_orm::{
+    sea_query::Expr,
+    ActiveModelTrait,
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, EntityTrait, QueryFilter,
+};
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+
+use crate::{
+    dto::auth::{OneTimeToken, TokenPurpose},
+    util::now_utc,
+    DbConn,
+};
+
+use super::error::DbResult;
+
+impl OneTimeToken {
+    pub(crate) async fn new(
+        user_uuid: Uuid,
+        purpose: TokenPurpose,
+        token_hash: String,
+        expiry_date: PrimitiveDateTime,
+        db: &DbConn,
+    ) -> DbResult<Self> {
+        let active_one_time_token = ActiveModelOneTimeToken {
+            id: NotSet,
+            token_hash: Set(token_hash),
+            user_uuid: Set(user_uuid),
+            purpose: Set(purpose.as_str().to_string()),
+            expiry_date: Set(expiry_date),
+            created_at: Set(now_utc()),
+            attempts: Set(0),
+        };
+
+        let model_one_time_token: ModelOneTimeToken =
+            active_one_time_token.insert(db).await?;
+
+        Ok(model_one_time_token.into())
+    }
+
+    /// Deletes the token and returns it when it was still valid, a token can
+    /// only be consumed once
+    pub(crate) async fn consume(
+        token_hash: String,
+        purpose: TokenPurpose,
+        db: &DbConn,
+    ) -> DbResult<Option<Self>> {
+        let model_one_time_token = EntityOneTimeToken::find()
+            .filter(entity_one_time_token::Column::TokenHash.eq(token_hash))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .one(db)
+            .await?;
+
+        let Some(model_one_time_token) = model_one_time_token else {
+            return Ok(None);
+        };
+
+        let res = EntityOneTimeToken::delete_by_id(model_one_time_token.id)
+            .exec(db)
+            .await?;
+
+        // Someone else consumed it first
+        if res.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        if model_one_time_token.expiry_date < now_utc() {
+            return Ok(None);
+        }
+
+        Ok(Some(model_one_time_token.into()))
+    }
+
+    /// Counts an attempt against the token, false when it is unknown, expired
+    /// or out of attempts
+    pub(crate) async fn record_attempt(
+        token_hash: String,
+        purpose: TokenPurpose,
+        max_attempts: i32,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        // One conditional update, so concurrent attempts can't share a count
+        let res = EntityOneTimeToken::update_many()
+            .col_expr(
+                entity_one_time_token::Column::Attempts,
+                Expr::col(entity_one_time_token::Column::Attempts).add(1),
+            )
+            .filter(entity_one_time_token::Column::TokenHash.eq(token_hash))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .filter(entity_one_time_token::Column::Attempts.lt(max_attempts))
+            .filter(entity_one_time_token::Column::ExpiryDate.gt(now_utc()))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Checks a short code (e.g. typed over from a mail) for the user, the
+    /// code is consumed when it matches or the attempts run out
+    pub(crate) async fn verify_code(
+        user_uuid: Uuid,
+        purpose: TokenPurpose,
+        token_hash: String,
+        max_attempts: i32,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let model_one_time_token = EntityOneTimeToken::find()
+            .filter(entity_one_time_token::Column::UserUuid.eq(user_uuid))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .one(db)
+            .await?;
+
+        let Some(model_one_time_token) = model_one_time_token else {
+            return Ok(false);
+        };
+
+        // Count the attempt before comparing, so concurrent guesses can't
+        // exceed the limit
+        let res = EntityOneTimeToken::update_many()
+            .col_expr(
+                entity_one_time_token::Column::Attempts,
+                Expr::col(entity_one_time_token::Column::Attempts).add(1),
+            )
+            .filter(
+                entity_one_time_token::Column::Id.eq(model_one_time_token.id),
+            )
+            .filter(entity_one_time_token::Column::Attempts.lt(max_attempts))
+            .filter(entity_one_time_token::Column::ExpiryDate.gt(now_utc()))
+            .exec(db)
+            .await?;
+
+        // Expired or out of attempts
+        if res.rows_affected == 0 {
+            let _res =
+                EntityOneTimeToken::delete_by_id(model_one_time_token.id)
+                    .exec(db)
+                    .await?;
+
+            return Ok(false);
+        }
+
+        if model_one_time_token.token_hash != token_hash {
+            return Ok(false);
+        }
+
+        let res = EntityOneTimeToken::delete_by_id(model_one_time_token.id)
+            .exec(db)
+            .await?;
+
+        // Someone else consumed it first when nothing was deleted
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Whether a token was issued to the user after `since`, to rate limit
+    /// mailing new ones
+    pub(crate) async fn issued_since(
+        user_uuid: Uuid,
+        purpose: TokenPurpose,
+        since: PrimitiveDateTime,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let model_one_time_token = EntityOneTimeToken::find()
+            .filter(entity_one_time_token::Column::UserUuid.eq(user_uuid))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .filter(entity_one_time_token::Column::CreatedAt.gt(since))
+            .one(db)
+            .await?;
+
+        Ok(model_one_time_token.is_some())
+    }
+
+    pub(crate) async fn drop_expired_by_user_uuid(
+        user_uuid: Uuid,
+        purpose: TokenPurpose,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _res = EntityOneTimeToken::delete_many()
+            .filter(entity_one_time_token::Column::UserUuid.eq(user_uuid))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .filter(entity_one_time_token::Column::ExpiryDate.lt(now_utc()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn drop_by_user_uuid(
+        user_uuid: Uuid,
+        purpose: TokenPurpose,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _res = EntityOneTimeToken::delete_many()
+            .filter(entity_one_time_token::Column::UserUuid.eq(user_uuid))
+            .filter(entity_one_time_token::Column::Purpose.eq(purpose.as_str()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl From<ModelOneTimeToken> for OneTimeToken {
+    fn from(value: ModelOneTimeToken) -> Self {
+        Self {
+            user_uuid: value.user_uuid,
+        }
+    }
+}
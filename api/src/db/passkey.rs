@@ -0,0 +1,123 @@
+use entity::passkey::{
+    self as entity_passkey, ActiveModel as ActiveModelPasskey,
+    Entity as EntityPasskey, Model as ModelPasskey,
+};
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::{NotSet, Set, Unchanged},
+    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
+
+use crate::{dto::passkey::UserPasskey, util::now_utc, DbConn};
+
+use super::error::{DbError, DbResult};
+
+impl UserPasskey {
+    pub(crate) async fn create(
+        user_uuid: Uuid,
+        name: String,
+        passkey: Passkey,
+        db: &DbConn,
+    ) -> DbResult<Self> {
+        let active_passkey = ActiveModelPasskey {
+            id: NotSet,
+            user_uuid: Set(user_uuid),
+            credential_id: Set(passkey.cred_id().to_string()),
+            name: Set(name),
+            public_key: Set(serialize_passkey(&passkey)?),
+            sign_count: Set(0),
+            created_at: Set(now_utc()),
+            last_used_at: Set(None),
+        };
+
+        let model_passkey = active_passkey.insert(db).await?;
+
+        model_passkey.try_into()
+    }
+
+    pub(crate) async fn get_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<Vec<Self>> {
+        EntityPasskey::find()
+            .filter(entity_passkey::Column::UserUuid.eq(user_uuid))
+            .order_by_asc(entity_passkey::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub(crate) async fn count_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<u64> {
+        let count = EntityPasskey::find()
+            .filter(entity_passkey::Column::UserUuid.eq(user_uuid))
+            .count(db)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Stores the counter and flags after a successful authentication
+    pub(crate) async fn update_used(
+        self,
+        sign_count: u32,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _model_passkey = ActiveModelPasskey {
+            id: Unchanged(self.id),
+            public_key: Set(serialize_passkey(&self.passkey)?),
+            sign_count: Set(sign_count.into()),
+            last_used_at: Set(Some(now_utc())),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn drop_by_id(
+        id: i32,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityPasskey::delete_many()
+            .filter(entity_passkey::Column::Id.eq(id))
+            .filter(entity_passkey::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_passkey(passkey: &Passkey) -> DbResult<String> {
+    serde_json::to_string(passkey).map_err(|_| DbError::InvalidData)
+}
+
+impl TryFrom<ModelPasskey> for UserPasskey {
+    type Error = DbError;
+
+    fn try_from(value: ModelPasskey) -> Result<Self, Self::Error> {
+        let passkey = serde_json::from_str(&value.public_key)
+            .map_err(|_| DbError::InvalidData)?;
+
+        Ok(Self {
+            id: value.id,
+            name: value.name,
+            passkey,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use entity::recovery_code::{
+    self as entity_recovery_code, ActiveModel as ActiveModelRecoveryCode,
+    Entity as EntityRecoveryCode,
+};
+use sea_orm::{
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+};
+use uuid::Uuid;
+
+use crate::{dto::mfa::RecoveryCode, util::now_utc, DbConn};
+
+use super::error::DbResult;
+
+impl RecoveryCode {
+    /// Replaces all recovery codes of the user
+    pub(crate) async fn replace(
+        user_uuid: Uuid,
+        code_hashes: Vec<String>,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        Self::drop_by_user_uuid(user_uuid, db).await?;
+
+        let now = now_utc();
+        let active_recovery_codes =
+            code_hashes
+                .into_iter()
+                .map(|code_hash| ActiveModelRecoveryCode {
+                    id: NotSet,
+                    user_uuid: Set(user_uuid),
+                    code_hash: Set(code_hash),
+                    created_at: Set(now),
+                });
+
+        let _res = EntityRecoveryCode::insert_many(active_recovery_codes)
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes the code, returns `false` if it didn't exist
+    pub(crate) async fn consume(
+        user_uuid: Uuid,
+        code_hash: String,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let res = EntityRecoveryCode::delete_many()
+            .filter(entity_recovery_code::Column::UserUuid.eq(user_uuid))
+            .filter(entity_recovery_code::Column::CodeHash.eq(code_hash))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    pub(crate) async fn count_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<u64> {
+        let count = EntityRecoveryCode::find()
+            .filter(entity_recovery_code::Column::UserUuid.eq(user_uuid))
+            .count(db)
+            .await?;
+
+        Ok(count)
+    }
+
+    pub(crate) async fn drop_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _res = EntityRecoveryCode::delete_many()
+            .filter(entity_recovery_code::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use entity::{
 use sea_orm::{
     ActiveModelTrait,
     ActiveValue::{NotSet, Set},
-    ColumnTrait, EntityTrait, QueryFilter,
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
 };
 use time::PrimitiveDateTime;
 use uuid::Uuid;
@@ -16,6 +16,7 @@ use uuid::Uuid;
 use crate::{
     db::error::DbError,
     dto::{auth::RefreshToken, user::User},
+    util::now_utc,
     DbConn,
 };
 
@@ -59,6 +60,41 @@ impl RefreshToken {
         Ok((model_refresh_token.into(), model_user.into()))
     }
 
+    pub(crate) async fn get_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<Vec<Self>> {
+        let refresh_tokens = EntityRefresToken::find()
+            .filter(entity_refresh_token::Column::UserUuid.eq(user_uuid))
+            .filter(entity_refresh_token::Column::ExpiryDate.gt(now_utc()))
+            .order_by_asc(entity_refresh_token::Column::ExpiryDate)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(refresh_tokens)
+    }
+
+    pub(crate) async fn drop_by_id(
+        id: i32,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityRefresToken::delete_many()
+            .filter(entity_refresh_token::Column::Id.eq(id))
+            .filter(entity_refresh_token::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn drop_by_token(
         token: Uuid,
         db: &DbConn,
@@ -70,11 +106,40 @@ impl RefreshToken {
 
         Ok(())
     }
+
+    /// Ends every session of the user except `keep`
+    pub(crate) async fn drop_by_user_uuid_except(
+        user_uuid: Uuid,
+        keep: Option<i32>,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let mut query = EntityRefresToken::delete_many()
+            .filter(entity_refresh_token::Column::UserUuid.eq(user_uuid));
+        if let Some(keep) = keep {
+            query = query.filter(entity_refresh_token::Column::Id.ne(keep));
+        }
+        let _res = query.exec(db).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn drop_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _res = EntityRefresToken::delete_many()
+            .filter(entity_refresh_token::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
 }
 
 impl From<ModelRefreshToken> for RefreshToken {
     fn from(value: ModelRefreshToken) -> Self {
         Self {
+            id: value.id,
             token: value.token,
             user_uuid: value.user_uuid,
             expiry_date: value.expiry_date,
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use entity::{
+    permission::{Entity as EntityPermission, Model as ModelPermission},
+    role::{self as entity_role, Entity as EntityRole, Model as ModelRole},
+    user_role::{
+        self as entity_user_role, ActiveModel as ActiveModelUserRole,
+        Entity as EntityUserRole,
+    },
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, EntityTrait, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, Select,
+};
+use uuid::Uuid;
+
+use crate::{
+    dto::role::{Grants, Role},
+    DbConn,
+};
+
+use super::error::{DbError, DbResult};
+
+impl Role {
+    /// Every role with its permissions
+    pub(crate) async fn list(db: &DbConn) -> DbResult<Vec<Self>> {
+        Self::with_permissions(EntityRole::find(), db).await
+    }
+
+    pub(crate) async fn get_by_name(name: &str, db: &DbConn) -> DbResult<Self> {
+        let role = Self::with_permissions(
+            EntityRole::find().filter(entity_role::Column::Name.eq(name)),
+            db,
+        )
+        .await?
+        .pop()
+        .ok_or(DbError::NoResult)?;
+
+        Ok(role)
+    }
+
+    pub(crate) async fn get_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<Vec<Self>> {
+        Self::with_permissions(
+            EntityRole::find()
+                .join(
+                    JoinType::InnerJoin,
+                    entity_role::Relation::UserRole.def(),
+                )
+                .filter(entity_user_role::Column::UserUuid.eq(user_uuid)),
+            db,
+        )
+        .await
+    }
+
+    /// Role names of every given user, users without roles are missing
+    pub(crate) async fn names_by_user_uuids(
+        user_uuids: Vec<Uuid>,
+        db: &DbConn,
+    ) -> DbResult<HashMap<Uuid, Vec<String>>> {
+        let user_roles = EntityUserRole::find()
+            .find_also_related(EntityRole)
+            .filter(entity_user_role::Column::UserUuid.is_in(user_uuids))
+            .order_by_asc(entity_role::Column::Name)
+            .all(db)
+            .await?;
+
+        let mut names: HashMap<Uuid, Vec<String>> = HashMap::new();
+        for (user_role, role) in user_roles {
+            if let Some(role) = role {
+                names
+                    .entry(user_role.user_uuid)
+                    .or_default()
+                    .push(role.name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Assigning a role the user already has does nothing
+    pub(crate) async fn assign(
+        &self,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let active_user_role = ActiveModelUserRole {
+            user_uuid: Set(user_uuid),
+            role_id: Set(self.id),
+        };
+
+        match EntityUserRole::insert(active_user_role)
+            .exec_without_returning(db)
+            .await
+            .map_err(DbError::from)
+        {
+            Ok(_) | Err(DbError::UniqueViolation(_)) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) async fn unassign(
+        &self,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUserRole::delete_many()
+            .filter(entity_user_role::Column::UserUuid.eq(user_uuid))
+            .filter(entity_user_role::Column::RoleId.eq(self.id))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    async fn with_permissions(
+        select: Select<EntityRole>,
+        db: &DbConn,
+    ) -> DbResult<Vec<Self>> {
+        let roles = select
+            .order_by_asc(entity_role::Column::Name)
+            .find_with_related(EntityPermission)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(role, permissions)| Self::from_model(role, permissions))
+            .collect();
+
+        Ok(roles)
+    }
+
+    fn from_model(role: ModelRole, permissions: Vec<ModelPermission>) -> Self {
+        let mut permissions: Vec<String> = permissions
+            .into_iter()
+            .map(|permission| permission.name)
+            .collect();
+        permissions.sort_unstable();
+
+        Self {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            permissions,
+        }
+    }
+}
+
+impl Grants {
+    pub(crate) async fn get_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<Self> {
+        let roles = Role::get_by_user_uuid(user_uuid, db).await?;
+
+        Ok(roles.into_iter().collect())
+    }
+}
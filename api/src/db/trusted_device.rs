@@ -0,0 +1,119 @@
+use entity::trusted_device::{
+    self as entity_trusted_device, ActiveModel as ActiveModelTrustedDevice,
+    Entity as EntityTrustedDevice, Model as ModelTrustedDevice,
+};
+use sea_orm::{
+    sea_query::Expr,
+    ActiveModelTrait,
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+
+use crate::{dto::session::TrustedDevice, util::now_utc, DbConn};
+
+use super::error::{DbError, DbResult};
+
+impl TrustedDevice {
+    pub(crate) async fn new(
+        user_uuid: Uuid,
+        name: Option<String>,
+        expiry_date: PrimitiveDateTime,
+        db: &DbConn,
+    ) -> DbResult<Self> {
+        let active_trusted_device = ActiveModelTrustedDevice {
+            id: NotSet,
+            uuid: Set(Uuid::new_v4()),
+            user_uuid: Set(user_uuid),
+            name: Set(name),
+            created_at: Set(now_utc()),
+            last_used_at: Set(None),
+            expiry_date: Set(expiry_date),
+        };
+
+        let model_trusted_device: ModelTrustedDevice =
+            active_trusted_device.insert(db).await?;
+
+        Ok(model_trusted_device.into())
+    }
+
+    pub(crate) async fn get_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<Vec<Self>> {
+        let trusted_devices = EntityTrustedDevice::find()
+            .filter(entity_trusted_device::Column::UserUuid.eq(user_uuid))
+            .filter(entity_trusted_device::Column::ExpiryDate.gt(now_utc()))
+            .order_by_asc(entity_trusted_device::Column::CreatedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(trusted_devices)
+    }
+
+    /// Marks the device as used, false when it is unknown or expired
+    pub(crate) async fn update_used(
+        uuid: Uuid,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let res = EntityTrustedDevice::update_many()
+            .col_expr(
+                entity_trusted_device::Column::LastUsedAt,
+                Expr::value(now_utc()),
+            )
+            .filter(entity_trusted_device::Column::Uuid.eq(uuid))
+            .filter(entity_trusted_device::Column::UserUuid.eq(user_uuid))
+            .filter(entity_trusted_device::Column::ExpiryDate.gt(now_utc()))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    pub(crate) async fn drop_by_uuid(
+        uuid: Uuid,
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityTrustedDevice::delete_many()
+            .filter(entity_trusted_device::Column::Uuid.eq(uuid))
+            .filter(entity_trusted_device::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn drop_by_user_uuid(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let _res = EntityTrustedDevice::delete_many()
+            .filter(entity_trusted_device::Column::UserUuid.eq(user_uuid))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl From<ModelTrustedDevice> for TrustedDevice {
+    fn from(value: ModelTrustedDevice) -> Self {
+        Self {
+            uuid: value.uuid,
+            name: value.name,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+            expiry_date: value.expiry_date,
+        }
+    }
+}
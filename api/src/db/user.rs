@@ -1,24 +1,74 @@
+use std::time::Duration;
+
 use entity::user::{
     self as entity_user, ActiveModel as ActiveModelUser, Entity as EntityUser,
     Model as ModelUser,
 };
 use sea_orm::{
+    sea_query::{Expr, LikeExpr, SimpleExpr},
     ActiveModelTrait,
     ActiveValue::{NotSet, Set, Unchanged},
-    ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
+    ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder,
 };
+use time::PrimitiveDateTime;
 use uuid::Uuid;
 
 use crate::{
-    dto::user::{UpdateUserInput, User},
+    config::env::ACCOUNT_DELETION_GRACE_DAYS,
+    dto::{
+        admin::AdminUserFilter,
+        user::{AccountStatus, UpdateUserInput, User, UserIdentifier},
+    },
+    i18n::Locale,
     util::now_utc,
     DbConn,
 };
 
 use super::error::{DbError, DbResult};
 
-// TODO: Create `UserIdentifier` enum with the variants id/uuid/email
-// and use this instead of seperate identiefiers
+/// How long a deleted account can still be restored
+fn deletion_grace_period() -> Duration {
+    Duration::from_secs(*ACCOUNT_DELETION_GRACE_DAYS * 24 * 60 * 60)
+}
+
+/// Accounts deleted before this are past their grace period
+fn deletion_cutoff() -> PrimitiveDateTime {
+    now_utc() - deletion_grace_period()
+}
+
+/// Accounts past their grace period are gone, even before they're purged
+fn not_purged() -> Condition {
+    Condition::any()
+        .add(entity_user::Column::DeletedAt.is_null())
+        .add(entity_user::Column::DeletedAt.gt(deletion_cutoff()))
+}
+
+/// Matches the value literally inside a `LIKE` pattern
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if matches!(char, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+impl UserIdentifier {
+    fn condition(self) -> SimpleExpr {
+        match self {
+            Self::Uuid(uuid) => entity_user::Column::Uuid.eq(uuid),
+            Self::Email(email) => entity_user::Column::Email.eq(email),
+            Self::DisplayName(display_name) => {
+                entity_user::Column::Displayname.eq(display_name)
+            }
+        }
+    }
+}
+
 impl User {
     pub(crate) async fn create(self, db: &DbConn) -> DbResult<Self> {
         let active_model_user = ActiveModelUser {
@@ -26,6 +76,7 @@ impl User {
             displayname: Set(self.display_name),
             email: Set(self.email),
             password: Set(self.password),
+            locale: Set(self.locale.as_str().to_string()),
             created_at: Set(self.created_at),
             updated_at: Set(self.updated_at),
             ..Default::default()
@@ -36,25 +87,124 @@ impl User {
         Ok(model_user.into())
     }
 
-    pub(crate) async fn get_by_uuid(uuid: Uuid, db: &DbConn) -> DbResult<Self> {
-        let user: User = EntityUser::find()
-            .filter(entity_user::Column::Uuid.eq(uuid))
+    pub(crate) async fn get_by_identifier(
+        identifier: UserIdentifier,
+        db: &DbConn,
+    ) -> DbResult<Option<Self>> {
+        let user = EntityUser::find()
+            .filter(identifier.condition())
+            .filter(not_purged())
             .one(db)
             .await?
-            .ok_or(DbError::NoResult)?
-            .into();
+            .map(Into::into);
 
         Ok(user)
     }
 
+    /// Accounts waiting for deletion are already hidden from others
+    pub(crate) async fn get_visible_by_identifier(
+        identifier: UserIdentifier,
+        db: &DbConn,
+    ) -> DbResult<Option<Self>> {
+        let user = EntityUser::find()
+            .filter(identifier.condition())
+            .filter(entity_user::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+            .map(Into::into);
+
+        Ok(user)
+    }
+
+    /// A page of the users matching the filter, and how many match in total
+    pub(crate) async fn list(
+        filter: &AdminUserFilter,
+        db: &DbConn,
+    ) -> DbResult<(Vec<Self>, u64)> {
+        let mut condition = Condition::all().add(not_purged());
+        if let Some(ref email) = filter.email {
+            let pattern = format!("%{}%", escape_like(email));
+            condition = condition.add(
+                Expr::col(entity_user::Column::Email)
+                    .like(LikeExpr::new(pattern).escape('\\')),
+            );
+        }
+        if let Some(status) = filter.status {
+            condition =
+                condition.add(entity_user::Column::Status.eq(status.as_str()));
+        }
+        if let Some(created_after) = filter.created_after {
+            condition = condition
+                .add(entity_user::Column::CreatedAt.gte(created_after));
+        }
+        if let Some(created_before) = filter.created_before {
+            condition = condition
+                .add(entity_user::Column::CreatedAt.lt(created_before));
+        }
+        if let Some(last_login_after) = filter.last_login_after {
+            condition = condition
+                .add(entity_user::Column::LastLogin.gte(last_login_after));
+        }
+        if let Some(last_login_before) = filter.last_login_before {
+            condition = condition
+                .add(entity_user::Column::LastLogin.lt(last_login_before));
+        }
+
+        let paginator = EntityUser::find()
+            .filter(condition)
+            .order_by_asc(entity_user::Column::Id)
+            .paginate(db, filter.per_page);
+
+        let total = paginator.num_items().await?;
+        let users = paginator
+            .fetch_page(filter.page - 1)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok((users, total))
+    }
+
+    pub(crate) async fn get_by_uuid(uuid: Uuid, db: &DbConn) -> DbResult<Self> {
+        Self::get_by_identifier(UserIdentifier::Uuid(uuid), db)
+            .await?
+            .ok_or(DbError::NoResult)
+    }
+
+    pub(crate) async fn get_by_email(
+        email: String,
+        db: &DbConn,
+    ) -> DbResult<Option<Self>> {
+        Self::get_by_identifier(UserIdentifier::Email(email), db).await
+    }
+
     pub(crate) async fn update_by_uuid(
         uuid: Uuid,
         update_user_input: UpdateUserInput,
         db: &DbConn,
     ) -> DbResult<()> {
-        let displayname = update_user_input.display_name.map_or(NotSet, Set);
-        let email = update_user_input.email.map_or(NotSet, Set);
+        let profile = update_user_input.profile;
+        let displayname = profile.display_name.map_or(NotSet, Set);
+        // A new email has to be verified again
+        let email_verified_at =
+            profile.email.as_ref().map_or(NotSet, |_| Set(None));
+        // Trusted devices have to go through the second factor again
+        let security_stamp = if update_user_input.password.is_some()
+            || profile.email.is_some()
+        {
+            Set(Uuid::new_v4())
+        } else {
+            NotSet
+        };
+        let email = profile.email.map_or(NotSet, Set);
         let password = update_user_input.password.map_or(NotSet, Set);
+        let bio = profile.bio.map_or(NotSet, Set);
+        let timezone = profile.timezone.map_or(NotSet, Set);
+        let website = profile.website.map_or(NotSet, Set);
+        let locale = profile
+            .locale
+            .map_or(NotSet, |locale| Set(locale.as_str().to_string()));
 
         let mut upstream_user: ActiveModelUser = EntityUser::find()
             .filter(entity_user::Column::Uuid.eq(uuid))
@@ -67,29 +217,18 @@ impl User {
         upstream_user.displayname = displayname;
         upstream_user.email = email;
         upstream_user.password = password;
+        upstream_user.email_verified_at = email_verified_at;
+        upstream_user.security_stamp = security_stamp;
+        upstream_user.locale = locale;
+        upstream_user.bio = bio;
+        upstream_user.timezone = timezone;
+        upstream_user.website = website;
 
         upstream_user.update(db).await?;
 
         Ok(())
     }
 
-    pub(crate) async fn get_id_uuid_password_by_email(
-        email: String,
-        db: &DbConn,
-    ) -> DbResult<Option<(i32, Uuid, Option<String>)>> {
-        let res = EntityUser::find()
-            .filter(entity_user::Column::Email.eq(email))
-            .select_only()
-            .column(entity_user::Column::Id)
-            .column(entity_user::Column::Uuid)
-            .column(entity_user::Column::Password)
-            .into_tuple()
-            .one(db)
-            .await?;
-
-        Ok(res)
-    }
-
     pub(crate) async fn update_last_login(
         id: i32,
         db: &DbConn,
@@ -97,6 +236,8 @@ impl User {
         let _id = ActiveModelUser {
             id: Unchanged(id),
             last_login: Set(Some(now_utc())),
+            // Logging back in cancels a pending deletion
+            deleted_at: Set(None),
             ..Default::default()
         }
         .update(db)
@@ -104,6 +245,250 @@ impl User {
 
         Ok(())
     }
+
+    pub(crate) async fn update_totp(
+        uuid: Uuid,
+        totp_secret: Option<String>,
+        totp_enabled_at: Option<PrimitiveDateTime>,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(entity_user::Column::TotpSecret, Expr::value(totp_secret))
+            .col_expr(
+                entity_user::Column::TotpEnabledAt,
+                Expr::value(totp_enabled_at),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    /// Records the time step of an accepted code, false when the same or a
+    /// later step was already used
+    pub(crate) async fn update_totp_last_step(
+        uuid: Uuid,
+        step: i64,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let res = EntityUser::update_many()
+            .col_expr(entity_user::Column::TotpLastStep, Expr::value(step))
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .filter(
+                Condition::any()
+                    .add(entity_user::Column::TotpLastStep.is_null())
+                    .add(entity_user::Column::TotpLastStep.lt(step)),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    pub(crate) async fn update_mfa_email_enabled(
+        uuid: Uuid,
+        mfa_email_enabled: bool,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(
+                entity_user::Column::MfaEmailEnabled,
+                Expr::value(mfa_email_enabled),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    /// Makes trusted devices go through the second factor again, for
+    /// whenever the credentials change
+    pub(crate) async fn rotate_security_stamp(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(
+                entity_user::Column::SecurityStamp,
+                Expr::value(Uuid::new_v4()),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_mfa_passkey_enabled(
+        uuid: Uuid,
+        mfa_passkey_enabled: bool,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(
+                entity_user::Column::MfaPasskeyEnabled,
+                Expr::value(mfa_passkey_enabled),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_avatar_url(
+        uuid: Uuid,
+        avatar_url: Option<String>,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(entity_user::Column::AvatarUrl, Expr::value(avatar_url))
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_status(
+        uuid: Uuid,
+        status: AccountStatus,
+        status_reason: Option<String>,
+        suspended_until: Option<PrimitiveDateTime>,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(entity_user::Column::Status, Expr::value(status.as_str()))
+            .col_expr(
+                entity_user::Column::StatusReason,
+                Expr::value(status_reason),
+            )
+            .col_expr(
+                entity_user::Column::SuspendedUntil,
+                Expr::value(suspended_until),
+            )
+            .col_expr(
+                entity_user::Column::StatusChangedAt,
+                Expr::value(now_utc()),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn set_email_verified(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<()> {
+        let res = EntityUser::update_many()
+            .col_expr(
+                entity_user::Column::EmailVerifiedAt,
+                Expr::value(now_utc()),
+            )
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .exec(db)
+            .await?;
+
+        if res.rows_affected == 0 {
+            return Err(DbError::NoResult);
+        }
+
+        Ok(())
+    }
+
+    /// Marks the account for deletion, returns when it will be purged
+    pub(crate) async fn mark_deleted(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> DbResult<PrimitiveDateTime> {
+        let deleted_at = now_utc();
+
+        // Asking again doesn't push the purge back
+        let res = EntityUser::update_many()
+            .col_expr(entity_user::Column::DeletedAt, Expr::value(deleted_at))
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .filter(entity_user::Column::DeletedAt.is_null())
+            .exec(db)
+            .await?;
+
+        if res.rows_affected > 0 {
+            return Ok(deleted_at + deletion_grace_period());
+        }
+
+        let deleted_at = EntityUser::find()
+            .filter(entity_user::Column::Uuid.eq(uuid))
+            .one(db)
+            .await?
+            .and_then(|model_user| model_user.deleted_at)
+            .ok_or(DbError::NoResult)?;
+
+        Ok(deleted_at + deletion_grace_period())
+    }
+
+    /// Removes the accounts past their grace period, everything belonging to
+    /// them goes along through the foreign keys. Returns how many were purged
+    /// and the avatars they had, the files aren't in the db
+    pub(crate) async fn purge_deleted(
+        db: &DbConn,
+    ) -> DbResult<(u64, Vec<String>)> {
+        let cutoff = deletion_cutoff();
+
+        let avatar_urls = EntityUser::find()
+            .filter(entity_user::Column::DeletedAt.lte(cutoff))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|model_user| model_user.avatar_url)
+            .collect();
+
+        let res = EntityUser::delete_many()
+            .filter(entity_user::Column::DeletedAt.lte(cutoff))
+            .exec(db)
+            .await?;
+
+        Ok((res.rows_affected, avatar_urls))
+    }
+
+    /// Whether any user, deleted or not, still has this avatar
+    pub(crate) async fn avatar_in_use(
+        avatar_url: &str,
+        db: &DbConn,
+    ) -> DbResult<bool> {
+        let count = EntityUser::find()
+            .filter(entity_user::Column::AvatarUrl.eq(avatar_url))
+            .count(db)
+            .await?;
+
+        Ok(count > 0)
+    }
 }
 
 impl From<ModelUser> for User {
@@ -117,6 +502,38 @@ impl From<ModelUser> for User {
             last_login: value.last_login,
             created_at: value.created_at,
             updated_at: value.updated_at,
+            email_verified_at: value.email_verified_at,
+            totp_secret: value.totp_secret,
+            totp_enabled_at: value.totp_enabled_at,
+            totp_last_step: value.totp_last_step,
+            mfa_email_enabled: value.mfa_email_enabled,
+            mfa_passkey_enabled: value.mfa_passkey_enabled,
+            security_stamp: value.security_stamp,
+            // Unknown locales are left in the db in case they're added back
+            locale: Locale::from_tag(&value.locale).unwrap_or_default(),
+            bio: value.bio,
+            timezone: value.timezone,
+            website: value.website,
+            avatar_url: value.avatar_url,
+            // Unknown statuses are treated as disabled rather than active
+            status: AccountStatus::parse(&value.status)
+                .unwrap_or(AccountStatus::Disabled),
+            status_reason: value.status_reason,
+            suspended_until: value.suspended_until,
+            status_changed_at: value.status_changed_at,
+            deleted_at: value.deleted_at,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn like_wildcards_are_escaped() {
+        assert_eq!(escape_like("john.doe"), "john.doe");
+        assert_eq!(escape_like("100%_off"), r"100\%\_off");
+        assert_eq!(escape_like(r"back\slash"), r"back\\slash");
+    }
+}
@@ -1,2 +1,8 @@
+pub(crate) mod admin;
 pub(crate) mod auth;
+pub(crate) mod export;
+pub(crate) mod mfa;
+pub(crate) mod passkey;
+pub(crate) mod role;
+pub(crate) mod session;
 pub(crate) mod user;
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use super::user::{AccountStatus, ProfileFields, User};
+use crate::{
+    i18n::Locale,
+    util::{datetime::rfc3339, now_utc},
+};
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    20
+}
+
+/// Query of the user list, every filter is optional
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct AdminUserFilter {
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1))]
+    pub(crate) page: u64,
+    #[serde(default = "default_per_page")]
+    #[validate(range(min = 1, max = 100))]
+    pub(crate) per_page: u64,
+    /// Matches part of the email
+    pub(crate) email: Option<String>,
+    pub(crate) status: Option<AccountStatus>,
+    #[serde(default, with = "rfc3339::option")]
+    pub(crate) created_after: Option<PrimitiveDateTime>,
+    #[serde(default, with = "rfc3339::option")]
+    pub(crate) created_before: Option<PrimitiveDateTime>,
+    #[serde(default, with = "rfc3339::option")]
+    pub(crate) last_login_after: Option<PrimitiveDateTime>,
+    #[serde(default, with = "rfc3339::option")]
+    pub(crate) last_login_before: Option<PrimitiveDateTime>,
+}
+
+/// Everything but the secrets of a user
+#[derive(Debug, Serialize)]
+pub(crate) struct AdminUserPayload {
+    pub(crate) uuid: Uuid,
+    pub(crate) display_name: String,
+    pub(crate) email: String,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) email_verified_at: Option<PrimitiveDateTime>,
+    pub(crate) has_password: bool,
+    pub(crate) roles: Vec<String>,
+    pub(crate) status: AccountStatus,
+    pub(crate) status_reason: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) suspended_until: Option<PrimitiveDateTime>,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) status_changed_at: Option<PrimitiveDateTime>,
+    pub(crate) locale: Locale,
+    pub(crate) bio: Option<String>,
+    pub(crate) timezone: Option<String>,
+    pub(crate) website: Option<String>,
+    pub(crate) avatar_url: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) last_login: Option<PrimitiveDateTime>,
+    #[serde(with = "rfc3339")]
+    pub(crate) created_at: PrimitiveDateTime,
+    #[serde(with = "rfc3339")]
+    pub(crate) updated_at: PrimitiveDateTime,
+}
+
+impl AdminUserPayload {
+    pub(crate) fn new(value: User, roles: Vec<String>) -> Self {
+        Self {
+            uuid: value.uuid,
+            display_name: value.display_name,
+            email: value.email,
+            email_verified_at: value.email_verified_at,
+            has_password: value.password.is_some(),
+            roles,
+            status: value.status,
+            status_reason: value.status_reason,
+            suspended_until: value.suspended_until,
+            status_changed_at: value.status_changed_at,
+            locale: value.locale,
+            bio: value.bio,
+            timezone: value.timezone,
+            website: value.website,
+            avatar_url: value.avatar_url,
+            last_login: value.last_login,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct AdminUsersPayload {
+    pub(crate) users: Vec<AdminUserPayload>,
+    pub(crate) page: u64,
+    pub(crate) per_page: u64,
+    pub(crate) total: u64,
+}
+
+/// `UpdateUserInput` without the password, admins trigger a reset instead
+pub(crate) type AdminUpdateUserInput = ProfileFields;
+
+/// New status of a user, `suspended_until` only goes with a suspension and
+/// leaving it out suspends until the status is changed again
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_suspended_until"))]
+pub(crate) struct AdminStatusInput {
+    pub(crate) status: AccountStatus,
+    /// Shown to the user when they try to log in
+    #[validate(length(max = 500))]
+    pub(crate) reason: Option<String>,
+    #[serde(default, with = "rfc3339::option")]
+    pub(crate) suspended_until: Option<PrimitiveDateTime>,
+}
+
+fn validate_suspended_until(
+    input: &AdminStatusInput,
+) -> Result<(), ValidationError> {
+    match (input.status, input.suspended_until) {
+        (_, None) => Ok(()),
+        (AccountStatus::Suspended, Some(until)) if until > now_utc() => Ok(()),
+        _ => Err(ValidationError::new("suspended_until")),
+    }
+}
@@ -1,12 +1,23 @@
 use serde::{Deserialize, Serialize};
 use time::PrimitiveDateTime;
 use uuid::Uuid;
+use validator::Validate;
 
+use super::{
+    mfa::{MfaMethod, MfaPendingPayload},
+    role::Grants,
+    session::SubTrustedDeviceToken,
+    user::User,
+};
 use crate::{
     config::{
-        constant::{ACCESS_TOKEN_TIMEOUT, REFRESH_TOKEN_TIMEOUT},
-        env::{JWT_ACCESS_SECRET, JWT_REFRESH_SECRET},
+        constant::{
+            ACCESS_TOKEN_TIMEOUT, MAGIC_LINK_TOKEN_TIMEOUT,
+            REFRESH_TOKEN_TIMEOUT,
+        },
+        env::{JWT_ACCESS_SECRET, JWT_MAGIC_LINK_SECRET, JWT_REFRESH_SECRET},
     },
+    i18n::Locale,
     util::jwt::{ClaimsEncoded, ClaimsSubTrait},
 };
 
@@ -25,9 +36,27 @@ impl ClaimsSubTrait for SubRefreshToken {
     }
 }
 
+impl ClaimsSubTrait for SubMagicLinkToken {
+    const DURATION: u64 = MAGIC_LINK_TOKEN_TIMEOUT;
+
+    fn secret<'a>() -> &'a [u8] {
+        &JWT_MAGIC_LINK_SECRET
+    }
+}
+
 impl SubAccesToken {
-    pub(crate) fn new(user_uuid: Uuid) -> Self {
-        Self { user_uuid }
+    pub(crate) fn new(
+        user_uuid: Uuid,
+        locale: Locale,
+        grants: Grants,
+        session: i32,
+    ) -> Self {
+        Self {
+            user_uuid,
+            locale,
+            grants,
+            session: Some(session),
+        }
     }
 }
 
@@ -37,11 +66,34 @@ impl SubRefreshToken {
     }
 }
 
+impl SubMagicLinkToken {
+    pub(crate) fn new(token: String) -> Self {
+        Self { token }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct LoginPayload {
     pub(crate) refresh_token: ClaimsEncoded<SubRefreshToken>,
     #[serde(flatten)]
     pub(crate) access_token: RefreshPayload,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) trusted_device_token:
+        Option<ClaimsEncoded<SubTrustedDeviceToken>>,
+}
+
+/// Outcome of authenticating with a first factor
+#[derive(Debug)]
+pub(crate) enum LoginStep {
+    Complete(Box<User>),
+    MfaRequired(Uuid, Vec<MfaMethod>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum LoginResponse {
+    Complete(LoginPayload),
+    MfaRequired(MfaPendingPayload),
 }
 
 #[derive(Debug, Serialize)]
@@ -60,10 +112,31 @@ pub(crate) struct SubRefreshToken {
 pub(crate) struct SubAccesToken {
     #[serde(rename = "user_uuid")]
     pub(crate) user_uuid: Uuid,
+    /// Stored language of the user, tokens issued before it existed get the
+    /// default
+    #[serde(default)]
+    pub(crate) locale: Locale,
+    /// Roles and permissions at the time of issuing, for clients to adapt
+    /// their ui. `extractor::Guard` checks the db so revoking takes effect
+    /// right away
+    #[serde(flatten)]
+    pub(crate) grants: Grants,
+    /// Id of the refresh token this was issued for, tokens issued before it
+    /// existed have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) session: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SubMagicLinkToken {
+    #[serde(rename = "magic_link_token")]
+    pub(crate) token: String,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct RefreshToken {
+    #[serde(skip_serializing)]
+    pub(crate) id: i32,
     #[serde(rename = "refresh_token")]
     pub(crate) token: Uuid,
     #[serde(skip_serializing)]
@@ -77,3 +150,55 @@ pub(crate) struct RefreshTokenInput {
     #[serde(rename = "refresh_token")]
     pub(crate) token: ClaimsEncoded<SubRefreshToken>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    MagicLink,
+    MfaEmail,
+    MfaLogin,
+}
+
+impl TokenPurpose {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::MagicLink => "magic_link",
+            TokenPurpose::MfaEmail => "mfa_email",
+            TokenPurpose::MfaLogin => "mfa_login",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct OneTimeToken {
+    pub(crate) user_uuid: Uuid,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ForgotPasswordInput {
+    #[validate(email)]
+    pub(crate) email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ResetPasswordInput {
+    pub(crate) token: String,
+    #[validate(length(min = 6))]
+    pub(crate) password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct MagicLinkInput {
+    #[validate(email)]
+    pub(crate) email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MagicLinkLoginInput {
+    pub(crate) token: ClaimsEncoded<SubMagicLinkToken>,
+    pub(crate) trusted_device_token:
+        Option<ClaimsEncoded<SubTrustedDeviceToken>>,
+}
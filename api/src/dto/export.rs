@@ -0,0 +1,82 @@
+use serde::Serialize;
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+
+use super::{
+    mfa::MfaSettingsPayload,
+    passkey::PasskeyPayload,
+    session::{SessionPayload, TrustedDevicePayload},
+    user::{AccountStatus, User},
+};
+use crate::{i18n::Locale, util::datetime::rfc3339};
+
+/// Everything stored about a user, for access requests
+#[derive(Debug, Serialize)]
+pub(crate) struct UserExport {
+    #[serde(with = "rfc3339")]
+    pub(crate) generated_at: PrimitiveDateTime,
+    pub(crate) user: UserExportProfile,
+    pub(crate) sessions: Vec<SessionPayload>,
+    pub(crate) trusted_devices: Vec<TrustedDevicePayload>,
+    pub(crate) passkeys: Vec<PasskeyPayload>,
+    pub(crate) mfa: MfaSettingsPayload,
+}
+
+/// The user row, secrets are only mentioned by whether they're set
+#[derive(Debug, Serialize)]
+pub(crate) struct UserExportProfile {
+    pub(crate) uuid: Uuid,
+    pub(crate) display_name: String,
+    pub(crate) email: String,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) email_verified_at: Option<PrimitiveDateTime>,
+    pub(crate) has_password: bool,
+    pub(crate) locale: Locale,
+    pub(crate) bio: Option<String>,
+    pub(crate) timezone: Option<String>,
+    pub(crate) website: Option<String>,
+    pub(crate) avatar_url: Option<String>,
+    pub(crate) roles: Vec<String>,
+    pub(crate) status: AccountStatus,
+    pub(crate) status_reason: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) suspended_until: Option<PrimitiveDateTime>,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) status_changed_at: Option<PrimitiveDateTime>,
+    /// Set while the account is waiting to be purged
+    #[serde(with = "rfc3339::option")]
+    pub(crate) deleted_at: Option<PrimitiveDateTime>,
+    // There's no login history besides the last one
+    #[serde(with = "rfc3339::option")]
+    pub(crate) last_login: Option<PrimitiveDateTime>,
+    #[serde(with = "rfc3339")]
+    pub(crate) created_at: PrimitiveDateTime,
+    #[serde(with = "rfc3339")]
+    pub(crate) updated_at: PrimitiveDateTime,
+}
+
+impl UserExportProfile {
+    pub(crate) fn new(value: User, roles: Vec<String>) -> Self {
+        Self {
+            uuid: value.uuid,
+            display_name: value.display_name,
+            email: value.email,
+            email_verified_at: value.email_verified_at,
+            has_password: value.password.is_some(),
+            locale: value.locale,
+            bio: value.bio,
+            timezone: value.timezone,
+            website: value.website,
+            avatar_url: value.avatar_url,
+            roles,
+            status: value.status,
+            status_reason: value.status_reason,
+            suspended_until: value.suspended_until,
+            status_changed_at: value.status_changed_at,
+            deleted_at: value.deleted_at,
+            last_login: value.last_login,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::{constant::MFA_TOKEN_TIMEOUT, env::JWT_MFA_SECRET},
+    util::jwt::{ClaimsEncoded, ClaimsSubTrait},
+};
+
+impl ClaimsSubTrait for SubMfaToken {
+    const DURATION: u64 = MFA_TOKEN_TIMEOUT;
+
+    fn secret<'a>() -> &'a [u8] {
+        &JWT_MFA_SECRET
+    }
+}
+
+impl SubMfaToken {
+    pub(crate) fn new(user_uuid: Uuid) -> Self {
+        Self {
+            user_uuid,
+            jti: Uuid::new_v4(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MfaMethod {
+    Totp,
+    RecoveryCode,
+    Passkey,
+    Email,
+}
+
+/// Proves the first factor was valid, exchanged for the usual tokens once
+/// the second factor is verified
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SubMfaToken {
+    #[serde(rename = "mfa_user_uuid")]
+    pub(crate) user_uuid: Uuid,
+    /// Tracked server side, so the token is single-use and its attempts are
+    /// counted
+    pub(crate) jti: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct MfaPendingPayload {
+    pub(crate) mfa_token: ClaimsEncoded<SubMfaToken>,
+    pub(crate) methods: Vec<MfaMethod>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MfaLoginInput {
+    pub(crate) mfa_token: ClaimsEncoded<SubMfaToken>,
+    pub(crate) method: MfaMethod,
+    pub(crate) code: String,
+    /// Skip the second factor on this device for the next logins
+    #[serde(default)]
+    pub(crate) remember_device: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TotpEnrollmentPayload {
+    pub(crate) secret: String,
+    pub(crate) otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ConfirmTotpInput {
+    #[validate(length(equal = 6))]
+    pub(crate) code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RecoveryCodesPayload {
+    pub(crate) recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MfaEmailInput {
+    pub(crate) mfa_token: ClaimsEncoded<SubMfaToken>,
+}
+
+/// Which second factors are enabled for the user
+#[derive(Debug, Serialize)]
+pub(crate) struct MfaSettingsPayload {
+    pub(crate) totp: bool,
+    pub(crate) email: bool,
+    pub(crate) passkey: bool,
+    pub(crate) passkeys: u64,
+    pub(crate) recovery_codes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct UpdateMfaSettingsInput {
+    pub(crate) email: Option<bool>,
+    /// Registered passkeys are accepted as second factor
+    pub(crate) passkey: Option<bool>,
+    pub(crate) current_password: Option<String>,
+}
+
+/// Single-use codes to log in when the authenticator is lost
+#[derive(Debug)]
+pub(crate) struct RecoveryCode;
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+use validator::Validate;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, Passkey, PublicKeyCredential,
+    RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+
+use super::mfa::SubMfaToken;
+use crate::util::{datetime::rfc3339, jwt::ClaimsEncoded};
+
+#[derive(Debug)]
+pub(crate) struct UserPasskey {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) passkey: Passkey,
+    pub(crate) created_at: PrimitiveDateTime,
+    pub(crate) last_used_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PasskeyPayload {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    #[serde(with = "rfc3339")]
+    pub(crate) created_at: PrimitiveDateTime,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) last_used_at: Option<PrimitiveDateTime>,
+}
+
+impl From<UserPasskey> for PasskeyPayload {
+    fn from(value: UserPasskey) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PasskeyRegistrationPayload {
+    pub(crate) ceremony_id: Uuid,
+    pub(crate) options: CreationChallengeResponse,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct FinishPasskeyRegistrationInput {
+    pub(crate) ceremony_id: Uuid,
+    #[validate(length(min = 1, max = 64))]
+    pub(crate) name: String,
+    pub(crate) credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PasskeyAuthenticationPayload {
+    pub(crate) ceremony_id: Uuid,
+    pub(crate) options: RequestChallengeResponse,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct PasskeyLoginInput {
+    #[validate(email)]
+    pub(crate) email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FinishPasskeyLoginInput {
+    pub(crate) ceremony_id: Uuid,
+    pub(crate) credential: PublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PasskeyMfaInput {
+    pub(crate) mfa_token: ClaimsEncoded<SubMfaToken>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FinishPasskeyMfaInput {
+    pub(crate) mfa_token: ClaimsEncoded<SubMfaToken>,
+    pub(crate) ceremony_id: Uuid,
+    pub(crate) credential: PublicKeyCredential,
+    #[serde(default)]
+    pub(crate) remember_device: bool,
+}
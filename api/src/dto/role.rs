@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Something a role allows, required by handlers through `extractor::Guard`
+pub(crate) trait Permission {
+    /// Name in the `permission` table
+    const NAME: &'static str;
+}
+
+macro_rules! permissions {
+    ($($(#[$meta:meta])* $permission:ident = $name:literal;)*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug)]
+            pub(crate) struct $permission;
+
+            impl Permission for $permission {
+                const NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+permissions! {
+    /// View, edit, disable and log out users
+    UsersManage = "users.manage";
+    /// Assign roles to users
+    RolesManage = "roles.manage";
+}
+
+/// A named set of permissions
+#[derive(Debug, Serialize)]
+pub(crate) struct Role {
+    #[serde(skip_serializing)]
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) permissions: Vec<String>,
+}
+
+/// The roles of a user and every permission they grant
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub(crate) struct Grants {
+    #[serde(default)]
+    pub(crate) roles: Vec<String>,
+    #[serde(default)]
+    pub(crate) permissions: Vec<String>,
+}
+
+impl Grants {
+    pub(crate) fn allows<P: Permission>(&self) -> bool {
+        self.permissions
+            .iter()
+            .any(|permission| permission == P::NAME)
+    }
+}
+
+impl FromIterator<Role> for Grants {
+    fn from_iter<T: IntoIterator<Item = Role>>(iter: T) -> Self {
+        let mut grants = Self::default();
+        for role in iter {
+            grants.roles.push(role.name);
+            grants.permissions.extend(role.permissions);
+        }
+        grants.permissions.sort_unstable();
+        grants.permissions.dedup();
+
+        grants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(name: &str, permissions: &[&str]) -> Role {
+        Role {
+            id: 0,
+            name: name.to_string(),
+            description: None,
+            permissions: permissions.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn grants_allow_permissions_of_any_role() {
+        let grants: Grants = [
+            role("admin", &[UsersManage::NAME, RolesManage::NAME]),
+            role("support", &[UsersManage::NAME]),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(grants.roles, ["admin", "support"]);
+        assert_eq!(grants.permissions, [RolesManage::NAME, UsersManage::NAME]);
+        assert!(grants.allows::<UsersManage>());
+        assert!(grants.allows::<RolesManage>());
+    }
+
+    #[test]
+    fn grants_deny_permissions_of_no_role() {
+        let grants: Grants = [role("support", &[UsersManage::NAME])]
+            .into_iter()
+            .collect();
+
+        assert!(grants.allows::<UsersManage>());
+        assert!(!grants.allows::<RolesManage>());
+    }
+
+    #[test]
+    fn no_roles_allow_nothing() {
+        let grants = Grants::default();
+
+        assert!(!grants.allows::<UsersManage>());
+        assert!(!grants.allows::<RolesManage>());
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+use uuid::Uuid;
+
+use super::auth::RefreshToken;
+use crate::{
+    config::{
+        constant::TRUSTED_DEVICE_TIMEOUT, env::JWT_TRUSTED_DEVICE_SECRET,
+    },
+    util::{datetime::rfc3339, jwt::ClaimsSubTrait},
+};
+
+impl ClaimsSubTrait for SubTrustedDeviceToken {
+    const DURATION: u64 = TRUSTED_DEVICE_TIMEOUT;
+
+    fn secret<'a>() -> &'a [u8] {
+        &JWT_TRUSTED_DEVICE_SECRET
+    }
+}
+
+impl SubTrustedDeviceToken {
+    pub(crate) fn new(
+        device_uuid: Uuid,
+        user_uuid: Uuid,
+        security_stamp: Uuid,
+    ) -> Self {
+        Self {
+            device_uuid,
+            user_uuid,
+            security_stamp,
+        }
+    }
+}
+
+/// Lets a device skip the second factor until it expires, is revoked or the
+/// security stamp of the user changes
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SubTrustedDeviceToken {
+    #[serde(rename = "trusted_device_uuid")]
+    pub(crate) device_uuid: Uuid,
+    pub(crate) user_uuid: Uuid,
+    pub(crate) security_stamp: Uuid,
+}
+
+#[derive(Debug)]
+pub(crate) struct TrustedDevice {
+    pub(crate) uuid: Uuid,
+    pub(crate) name: Option<String>,
+    pub(crate) created_at: PrimitiveDateTime,
+    pub(crate) last_used_at: Option<PrimitiveDateTime>,
+    pub(crate) expiry_date: PrimitiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TrustedDevicePayload {
+    pub(crate) uuid: Uuid,
+    pub(crate) name: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub(crate) created_at: PrimitiveDateTime,
+    #[serde(with = "rfc3339::option")]
+    pub(crate) last_used_at: Option<PrimitiveDateTime>,
+    #[serde(with = "rfc3339")]
+    pub(crate) expires_at: PrimitiveDateTime,
+}
+
+impl From<TrustedDevice> for TrustedDevicePayload {
+    fn from(value: TrustedDevice) -> Self {
+        Self {
+            uuid: value.uuid,
+            name: value.name,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+            expires_at: value.expiry_date,
+        }
+    }
+}
+
+/// A refresh token, without the token itself
+#[derive(Debug, Serialize)]
+pub(crate) struct SessionPayload {
+    pub(crate) id: i32,
+    #[serde(with = "rfc3339")]
+    pub(crate) expires_at: PrimitiveDateTime,
+}
+
+impl From<RefreshToken> for SessionPayload {
+    fn from(value: RefreshToken) -> Self {
+        Self {
+            id: value.id,
+            expires_at: value.expiry_date,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SessionsPayload {
+    pub(crate) sessions: Vec<SessionPayload>,
+    pub(crate) trusted_devices: Vec<TrustedDevicePayload>,
+}
@@ -1,9 +1,18 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use time::PrimitiveDateTime;
 use uuid::Uuid;
-use validator::Validate;
+use validator::{validate_length, Validate, ValidationError, ValidationErrors};
 
-use crate::util::now_utc;
+use super::session::SubTrustedDeviceToken;
+use crate::{
+    i18n::Locale,
+    util::{
+        datetime::rfc3339, jwt::ClaimsEncoded, now_utc, validate_http_url,
+        validate_timezone,
+    },
+};
 
 #[derive(Debug, Deserialize, Validate)]
 pub(crate) struct LoginUserInput {
@@ -13,6 +22,8 @@ pub(crate) struct LoginUserInput {
     #[validate(email)]
     pub(crate) email: String,
     pub(crate) password: Option<String>,
+    pub(crate) trusted_device_token:
+        Option<ClaimsEncoded<SubTrustedDeviceToken>>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -23,21 +34,99 @@ pub(crate) struct RegisterUserInput {
     pub(crate) email: String,
     #[validate(length(min = 6))]
     pub(crate) password: Option<String>,
+    /// Defaults to the language the request was made in
+    pub(crate) locale: Option<Locale>,
 }
 
+/// What both the user and an admin can change
 #[derive(Debug, Deserialize, Validate, PartialEq, Default)]
-pub(crate) struct UpdateUserInput {
+pub(crate) struct ProfileFields {
     #[validate(length(min = 2, max = 20))]
     pub(crate) display_name: Option<String>,
     #[validate(email)]
     pub(crate) email: Option<String>,
-    #[validate(length(min = 6))]
+    pub(crate) locale: Option<Locale>,
+    // The profile fields are cleared with `null`
+    #[validate(length(max = 500))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub(crate) bio: Option<Option<String>>,
+    #[validate(custom = "validate_timezone")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub(crate) timezone: Option<Option<String>>,
+    #[validate(custom = "validate_http_url", length(max = 2048))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub(crate) website: Option<Option<String>>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Default)]
+pub(crate) struct UpdateUserInput {
+    #[serde(flatten)]
+    pub(crate) profile: ProfileFields,
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         with = "::serde_with::rust::double_option"
     )]
     pub(crate) password: Option<Option<String>>,
+    /// Required when changing `email` or `password` on an account that has a
+    /// password
+    pub(crate) current_password: Option<String>,
+}
+
+impl Validate for UpdateUserInput {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        // The profile is flattened, so are its errors
+        let mut errors = self.profile.validate().err().unwrap_or_default();
+
+        if let Some(Some(ref password)) = self.password {
+            if !validate_length(password, Some(6), None, None) {
+                let mut error = ValidationError::new("length");
+                error.add_param("min".into(), &6);
+                errors.add("password", error);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl From<ProfileFields> for UpdateUserInput {
+    fn from(profile: ProfileFields) -> Self {
+        Self {
+            profile,
+            ..Default::default()
+        }
+    }
+}
+
+impl UpdateUserInput {
+    pub(crate) fn is_sensitive(&self) -> bool {
+        self.profile.email.is_some() || self.password.is_some()
+    }
+}
+
+/// The unique keys a user can be looked up by
+#[derive(Debug, Clone)]
+pub(crate) enum UserIdentifier {
+    Uuid(Uuid),
+    Email(String),
+    DisplayName(String),
 }
 
 #[derive(Debug, Serialize, Validate)]
@@ -56,6 +145,80 @@ pub(crate) struct User {
     pub(crate) created_at: PrimitiveDateTime,
     #[serde(skip)]
     pub(crate) updated_at: PrimitiveDateTime,
+    #[serde(skip)]
+    pub(crate) email_verified_at: Option<PrimitiveDateTime>,
+    #[serde(skip)]
+    pub(crate) totp_secret: Option<String>,
+    #[serde(skip)]
+    pub(crate) totp_enabled_at: Option<PrimitiveDateTime>,
+    /// Time step of the last accepted code, codes can't be used twice
+    #[serde(skip)]
+    pub(crate) totp_last_step: Option<i64>,
+    #[serde(skip)]
+    pub(crate) mfa_email_enabled: bool,
+    /// Registered passkeys only count as second factor once opted in
+    #[serde(skip)]
+    pub(crate) mfa_passkey_enabled: bool,
+    /// Changes whenever the credentials do, invalidating trusted devices
+    #[serde(skip)]
+    pub(crate) security_stamp: Uuid,
+    /// Language of errors when the request doesn't ask for one, mails are
+    /// only sent in English
+    pub(crate) locale: Locale,
+    pub(crate) bio: Option<String>,
+    /// IANA name, e.g. `Europe/Brussels`
+    pub(crate) timezone: Option<String>,
+    pub(crate) website: Option<String>,
+    pub(crate) avatar_url: Option<String>,
+    /// Only active users can log in
+    #[serde(skip)]
+    pub(crate) status: AccountStatus,
+    /// Why an admin changed the status, shown to the user
+    #[serde(skip)]
+    pub(crate) status_reason: Option<String>,
+    /// End of the suspension, none is until an admin lifts it
+    #[serde(skip)]
+    pub(crate) suspended_until: Option<PrimitiveDateTime>,
+    #[serde(skip)]
+    pub(crate) status_changed_at: Option<PrimitiveDateTime>,
+    /// When the user asked for the account to be deleted, it is purged after
+    /// a grace period
+    #[serde(skip)]
+    pub(crate) deleted_at: Option<PrimitiveDateTime>,
+}
+
+/// Whether a user can use their account, set by admins
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AccountStatus {
+    #[default]
+    Active,
+    Disabled,
+    Suspended,
+    Banned,
+}
+
+impl AccountStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Disabled => "disabled",
+            AccountStatus::Suspended => "suspended",
+            AccountStatus::Banned => "banned",
+        }
+    }
+
+    pub(crate) fn parse(status: &str) -> Option<Self> {
+        match status {
+            "active" => Some(AccountStatus::Active),
+            "disabled" => Some(AccountStatus::Disabled),
+            "suspended" => Some(AccountStatus::Suspended),
+            "banned" => Some(AccountStatus::Banned),
+            _ => None,
+        }
+    }
 }
 
 impl Default for User {
@@ -71,6 +234,120 @@ impl Default for User {
             last_login: None,
             created_at: now,
             updated_at: now,
+            email_verified_at: None,
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            mfa_email_enabled: false,
+            mfa_passkey_enabled: false,
+            security_stamp: Uuid::new_v4(),
+            locale: Locale::default(),
+            bio: None,
+            timezone: None,
+            website: None,
+            avatar_url: None,
+            status: AccountStatus::default(),
+            status_reason: None,
+            suspended_until: None,
+            status_changed_at: None,
+            deleted_at: None,
         }
     }
 }
+
+/// What anyone can see of a user, the email only to the user themselves
+#[derive(Debug, Serialize)]
+pub(crate) struct PublicUserPayload {
+    pub(crate) uuid: Uuid,
+    pub(crate) display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) email: Option<String>,
+    pub(crate) bio: Option<String>,
+    pub(crate) website: Option<String>,
+    pub(crate) avatar_url: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub(crate) created_at: PrimitiveDateTime,
+}
+
+impl PublicUserPayload {
+    pub(crate) fn new(user: User, show_email: bool) -> Self {
+        Self {
+            uuid: user.uuid,
+            display_name: user.display_name,
+            email: show_email.then_some(user.email),
+            bio: user.bio,
+            website: user.website,
+            avatar_url: user.avatar_url,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct AvatarPayload {
+    pub(crate) avatar_url: String,
+    /// Url of the thumbnail in every size, by its width in pixels
+    pub(crate) sizes: BTreeMap<u32, String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct VerifyEmailInput {
+    pub(crate) token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ResendEmailVerificationInput {
+    #[validate(email)]
+    pub(crate) email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub(crate) struct ReauthenticateInput {
+    pub(crate) current_password: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct AccountDeletionPayload {
+    /// Logging in before then cancels the deletion
+    #[serde(with = "rfc3339")]
+    pub(crate) purge_at: PrimitiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_fields_are_flattened() {
+        let input: UpdateUserInput = serde_json::from_str(
+            r#"{"bio": null, "website": "https://example.com", "password": "secret"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(input.profile.bio, Some(None));
+        assert_eq!(input.profile.timezone, None);
+        assert_eq!(
+            input.profile.website,
+            Some(Some("https://example.com".to_string()))
+        );
+        assert_eq!(input.password, Some(Some("secret".to_string())));
+    }
+
+    #[test]
+    fn profile_errors_keep_their_field_names() {
+        let input = UpdateUserInput {
+            profile: ProfileFields {
+                display_name: Some("x".to_string()),
+                ..Default::default()
+            },
+            password: Some(Some("short".to_string())),
+            ..Default::default()
+        };
+
+        let errors = input.validate().unwrap_err();
+        let mut fields: Vec<_> = errors.errors().keys().copied().collect();
+        fields.sort_unstable();
+
+        assert_eq!(fields, ["display_name", "password"]);
+    }
+}
@@ -1,18 +1,36 @@
-use axum::{http::StatusCode, Json};
-use serde_json::{json, Value};
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::StatusCode,
+};
+use image::ImageError;
 use thiserror::Error as ErrorTrait;
+use webauthn_rs::prelude::WebauthnError;
 
 pub(crate) use user::UserError;
 
-use crate::db::error::DbError;
+use crate::{
+    config::constant::{
+        AVATAR_DECODE_RETRY_AFTER, DATABASE_RETRY_AFTER,
+        PASSKEY_CEREMONY_RETRY_AFTER, PASSWORD_HASHING_RETRY_AFTER,
+    },
+    db::error::DbError,
+    mail::MailError,
+    storage::StorageError,
+};
 
 use self::user::PublicUserError;
 
+mod problem;
 mod user;
 
 #[derive(Debug, ErrorTrait)]
 pub(crate) enum PublicError {
-    #[error(transparent)]
+    #[error("validation failed")]
     Validation(#[from] validator::ValidationErrors),
 
     #[error(transparent)]
@@ -24,10 +42,114 @@ pub(crate) enum PublicError {
     #[error(transparent)]
     TypedHeaderRejection(axum::extract::rejection::TypedHeaderRejection),
 
+    #[error("{}", .0.body_text())]
+    JsonRejection(#[from] JsonRejection),
+
+    #[error("{}", .0.body_text())]
+    PathRejection(#[from] PathRejection),
+
+    #[error("{}", .0.body_text())]
+    QueryRejection(#[from] QueryRejection),
+
+    #[error("{}", .0.body_text())]
+    MultipartRejection(#[from] MultipartRejection),
+
+    #[error(transparent)]
+    Multipart(#[from] MultipartError),
+
+    #[error("missing avatar field")]
+    MissingAvatar,
+
+    #[error("unsupported image type")]
+    UnsupportedImage,
+
+    #[error("image larger than {0} bytes")]
+    ImageTooLarge(usize),
+
+    #[error("invalid image")]
+    InvalidImage,
+
+    #[error("not found")]
+    NotFound,
+
+    #[error("forbidden")]
+    Forbidden,
+
+    #[error("conflicts with the current state of the resource")]
+    Conflict,
+
+    #[error("service temporarily unavailable")]
+    Unavailable(Duration),
+
     #[error("internal error")]
     Internal,
 }
 
+impl PublicError {
+    /// Stable identifier for clients, unlike the message it never changes
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::Validation(_) => "validation_failed",
+            Self::User(err) => err.code(),
+            Self::Jsonwebtoken(_) => "invalid_bearer_token",
+            Self::TypedHeaderRejection(_) => "missing_bearer_token",
+            Self::JsonRejection(JsonRejection::MissingJsonContentType(_)) => {
+                "unsupported_media_type"
+            }
+            Self::JsonRejection(JsonRejection::JsonSyntaxError(_)) => {
+                "malformed_json"
+            }
+            Self::JsonRejection(_) => "invalid_body",
+            Self::PathRejection(_) => "invalid_path",
+            Self::QueryRejection(_) => "invalid_query",
+            Self::MultipartRejection(_) | Self::Multipart(_) => {
+                "invalid_multipart"
+            }
+            Self::MissingAvatar => "missing_avatar",
+            Self::UnsupportedImage => "unsupported_image_type",
+            Self::ImageTooLarge(_) => "image_too_large",
+            Self::InvalidImage => "invalid_image",
+            Self::NotFound => "not_found",
+            Self::Forbidden => "forbidden",
+            Self::Conflict => "conflict",
+            Self::Unavailable(_) => "service_unavailable",
+            Self::Internal => "internal_error",
+        }
+    }
+
+    pub(crate) fn status(&self) -> StatusCode {
+        match self {
+            Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::User(err) => err.into(),
+            Self::Jsonwebtoken(_) | Self::TypedHeaderRejection(_) => {
+                StatusCode::UNAUTHORIZED
+            }
+            Self::JsonRejection(rejection) => rejection.status(),
+            Self::PathRejection(rejection) => rejection.status(),
+            Self::QueryRejection(rejection) => rejection.status(),
+            Self::MultipartRejection(rejection) => rejection.status(),
+            Self::Multipart(_) | Self::MissingAvatar => StatusCode::BAD_REQUEST,
+            Self::UnsupportedImage => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::ImageTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::InvalidImage => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// When the request may be retried, sent along as `Retry-After`
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::User(err) => err.retry_after(),
+            Self::Unavailable(retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, ErrorTrait)]
 pub(crate) enum ErrorRepr {
     #[error(transparent)]
@@ -45,6 +167,11 @@ pub(crate) enum ErrorRepr {
     #[error(transparent)]
     MissingBearer(axum::extract::rejection::TypedHeaderRejection),
 
+    /// Only for the `Authorization` header, tokens in a body are an
+    /// `UserError::InvalidToken`
+    #[error("invalid bearer token: {0}")]
+    InvalidBearer(jsonwebtoken::errors::Error),
+
     #[error(transparent)]
     PasswordHash(#[from] password_hash::errors::Error),
 
@@ -53,10 +180,50 @@ pub(crate) enum ErrorRepr {
 
     #[error(transparent)]
     Validation(#[from] validator::ValidationErrors),
+
+    #[error(transparent)]
+    Mail(#[from] MailError),
+
+    #[error(transparent)]
+    TotpUrl(#[from] totp_rs::TotpUrlError),
+
+    #[error("invalid totp secret: {0:?}")]
+    TotpSecret(totp_rs::SecretParseError),
+
+    #[error(transparent)]
+    SystemTime(#[from] std::time::SystemTimeError),
+
+    #[error(transparent)]
+    Webauthn(#[from] WebauthnError),
+
+    #[error("password hashing queue is full")]
+    HashingPoolFull,
+
+    #[error("too many ongoing passkey ceremonies")]
+    CeremoniesFull,
+
+    #[error("too many avatars being decoded")]
+    AvatarDecodesFull,
+
+    #[error("unsupported image type")]
+    UnsupportedImage,
+
+    #[error("forbidden")]
+    Forbidden,
+
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    TaskJoin(#[from] tokio::task::JoinError),
 }
 
 pub(crate) type ResultRepr<T> = std::result::Result<T, ErrorRepr>;
-pub(crate) type ApiError = (StatusCode, Json<Value>);
+/// Rendered as an `application/problem+json` response
+pub(crate) type ApiError = PublicError;
 pub(crate) type ApiResult<T> = std::result::Result<T, ApiError>;
 
 impl From<ErrorRepr> for PublicError {
@@ -65,29 +232,128 @@ impl From<ErrorRepr> for PublicError {
 
         match err {
             ErrorRepr::Validation(err) => Self::Validation(err),
-            ErrorRepr::Jsonwebtoken(err) => Self::Jsonwebtoken(err),
+            ErrorRepr::InvalidBearer(err) => Self::Jsonwebtoken(err),
             ErrorRepr::MissingBearer(err) => Self::TypedHeaderRejection(err),
             ErrorRepr::User(err) => Self::User(err.into()),
+            ErrorRepr::Webauthn(err) if is_credential_error(&err) => {
+                Self::User(PublicUserError::InvalidPasskey)
+            }
+            ErrorRepr::HashingPoolFull => Self::Unavailable(
+                Duration::from_secs(PASSWORD_HASHING_RETRY_AFTER),
+            ),
+            ErrorRepr::CeremoniesFull => Self::Unavailable(
+                Duration::from_secs(PASSKEY_CEREMONY_RETRY_AFTER),
+            ),
+            ErrorRepr::AvatarDecodesFull => Self::Unavailable(
+                Duration::from_secs(AVATAR_DECODE_RETRY_AFTER),
+            ),
+            ErrorRepr::UnsupportedImage
+            | ErrorRepr::Image(ImageError::Unsupported(_)) => {
+                Self::UnsupportedImage
+            }
+            ErrorRepr::Image(
+                ImageError::Decoding(_) | ImageError::Limits(_),
+            ) => Self::InvalidImage,
+            ErrorRepr::Forbidden => Self::Forbidden,
+            ErrorRepr::Db(err) => err.into(),
+            ErrorRepr::SeaOrm(err) => DbError::from(err).into(),
             _ => Self::Internal,
         }
     }
 }
 
-impl From<ErrorRepr> for ApiError {
-    fn from(value: ErrorRepr) -> Self {
-        PublicError::from(value).into()
+/// Whether the credential sent by the client failed verification, anything
+/// else is a problem on our side
+fn is_credential_error(err: &WebauthnError) -> bool {
+    use WebauthnError::*;
+
+    matches!(
+        err,
+        InvalidClientDataType
+            | MismatchedChallenge
+            | ChallengeNotFound
+            | InvalidRPOrigin
+            | InvalidRPIDHash
+            | UserNotPresent
+            | UserNotVerified
+            | InvalidExtensions
+            | AuthenticatorDataMissingExtension
+            | MissingAttestationCredentialData
+            | ParseBase64Failure(_)
+            | ParseCBORFailure(_)
+            | ParseJSONFailure(_)
+            | ParseNOMFailure
+            | ParseInsufficientBytesAvailable
+            | COSEKeyInvalidCBORValue
+            | COSEKeyInvalidType
+            | COSEKeyEDUnsupported
+            | COSEKeyECDSAXYInvalid
+            | COSEKeyRSANEInvalid
+            | COSEKeyECDSAInvalidCurve
+            | COSEKeyEDDSAXInvalid
+            | COSEKeyEDDSAInvalidCurve
+            | COSEKeyInvalidAlgorithm
+            | CredentialMayNotBeHardwareBound
+            | CredentialInsecureCryptography
+            | CredentialAlreadyExists
+            | CredentialNotFound
+            | CredentialAlteredAlgFromRequest
+            | CredentialExcludedFromRequest
+            | CredentialPossibleCompromise
+            | CredentialBackupElligibilityInconsistent
+            | CredentialCrossOrigin
+            | AuthenticationFailure
+            | InvalidUserUniqueId
+    )
+}
+
+impl From<DbError> for PublicError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::NoResult => Self::NotFound,
+            DbError::UniqueViolation(_) | DbError::ForeignKeyViolation(_) => {
+                Self::Conflict
+            }
+            DbError::Unavailable(_) => {
+                Self::Unavailable(Duration::from_secs(DATABASE_RETRY_AFTER))
+            }
+            DbError::MissingRelation
+            | DbError::InvalidData
+            | DbError::Other(_) => Self::Internal,
+        }
     }
 }
 
-impl From<PublicError> for ApiError {
-    fn from(err: PublicError) -> Self {
-        let status = match err {
-            PublicError::Validation(_) => StatusCode::BAD_REQUEST,
-            PublicError::User(ref err) => err.into(),
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webauthn_errors_split_between_client_and_server() {
+        let err: PublicError =
+            ErrorRepr::Webauthn(WebauthnError::MismatchedChallenge).into();
+        assert!(matches!(
+            err,
+            PublicError::User(PublicUserError::InvalidPasskey)
+        ));
+
+        let err: PublicError =
+            ErrorRepr::Webauthn(WebauthnError::ChallengePersistenceError)
+                .into();
+        assert!(matches!(err, PublicError::Internal));
+    }
+
+    #[test]
+    fn only_bearer_tokens_are_unauthorized() {
+        use jsonwebtoken::errors::ErrorKind;
+
+        let err: PublicError =
+            ErrorRepr::InvalidBearer(ErrorKind::InvalidToken.into()).into();
+        assert_eq!(err.status(), StatusCode::UNAUTHORIZED);
 
-        let payload = json!({"message": err.to_string()});
-        (status, Json(payload))
+        // Failing to sign a token is our problem
+        let err: PublicError =
+            ErrorRepr::Jsonwebtoken(ErrorKind::InvalidRsaKey("").into()).into();
+        assert!(matches!(err, PublicError::Internal));
     }
 }
@@ -0,0 +1,250 @@
+use axum::{
+    http::{
+        header::{CONTENT_TYPE, RETRY_AFTER, WWW_AUTHENTICATE},
+        HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use time::format_description::well_known::Rfc3339;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use super::{user::PublicUserError, PublicError};
+use crate::{
+    config::constant::BEARER,
+    i18n::Locale,
+    middleware::{locale::current_locale, request_id::current_request_id},
+};
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// RFC 7807 problem details
+#[derive(Debug, Serialize)]
+struct Problem {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+impl PublicError {
+    /// Members specific to the problem type
+    fn extensions(&self, locale: Locale) -> Map<String, Value> {
+        let mut extensions = Map::new();
+
+        match self {
+            Self::Validation(errors) => {
+                let mut fields = Map::new();
+                field_errors(errors, None, locale, &mut fields);
+                extensions.insert("errors".to_string(), fields.into());
+            }
+            Self::User(PublicUserError::Conflict(fields)) => {
+                extensions.insert("fields".to_string(), fields.clone().into());
+            }
+            Self::ImageTooLarge(max_size) => {
+                extensions.insert("max_size".to_string(), (*max_size).into());
+            }
+            Self::User(
+                PublicUserError::AccountDisabled(reason)
+                | PublicUserError::AccountBanned(reason),
+            ) => {
+                if let Some(reason) = reason {
+                    extensions
+                        .insert("reason".to_string(), reason.clone().into());
+                }
+            }
+            Self::User(PublicUserError::AccountSuspended(reason, until)) => {
+                if let Some(reason) = reason {
+                    extensions
+                        .insert("reason".to_string(), reason.clone().into());
+                }
+                if let Some(until) = until
+                    .and_then(|until| until.assume_utc().format(&Rfc3339).ok())
+                {
+                    extensions
+                        .insert("suspended_until".to_string(), until.into());
+                }
+            }
+            _ => {}
+        }
+
+        extensions
+    }
+}
+
+/// Flattens the errors into `{field: [{code, message, params}]}`, nested
+/// fields are named `parent.field` and `parent[index].field`
+fn field_errors(
+    errors: &ValidationErrors,
+    prefix: Option<&str>,
+    locale: Locale,
+    fields: &mut Map<String, Value>,
+) {
+    for (field, kind) in errors.errors() {
+        let field = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let errors = errors
+                    .iter()
+                    .map(|error| field_error(error, locale))
+                    .collect();
+                fields.insert(field, Value::Array(errors));
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                field_errors(errors, Some(&field), locale, fields);
+            }
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    field_errors(
+                        errors,
+                        Some(&format!("{field}[{index}]")),
+                        locale,
+                        fields,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn field_error(error: &ValidationError, locale: Locale) -> Value {
+    // The rejected value is left out, it could be a password
+    let params: Map<String, Value> = error
+        .params
+        .iter()
+        .filter(|(name, _)| *name != "value")
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+
+    // The catalog goes first, custom messages are only written in English
+    let message = locale
+        .validation(&error.code, &params)
+        .or_else(|| error.message.as_ref().map(ToString::to_string))
+        .unwrap_or_else(|| locale.invalid_field().to_string());
+
+    json!({
+        "code": error.code,
+        "message": message,
+        "params": params,
+    })
+}
+
+impl IntoResponse for PublicError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::error!("internal error: {:?}", self);
+        }
+
+        let locale = current_locale();
+        let code = self.code();
+        let problem = Problem {
+            problem_type: format!("urn:playground:problem:{code}"),
+            title: status.canonical_reason().unwrap_or("Unknown error"),
+            status: status.as_u16(),
+            detail: locale
+                .error(code)
+                .map_or_else(|| self.to_string(), ToOwned::to_owned),
+            code,
+            request_id: current_request_id(),
+            extensions: self.extensions(locale),
+        };
+
+        let mut response = (status, Json(problem)).into_response();
+        let headers = response.headers_mut();
+
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        if let Some(retry_after) = self.retry_after() {
+            // Whole seconds, rounded up so the retry isn't too early
+            let seconds = retry_after.as_secs()
+                + u64::from(retry_after.subsec_nanos() > 0);
+            headers.insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        if matches!(self, Self::Jsonwebtoken(_) | Self::TypedHeaderRejection(_))
+        {
+            headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static(BEARER));
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Validate)]
+    struct Item {
+        #[validate(length(min = 1))]
+        name: String,
+    }
+
+    #[derive(Validate)]
+    struct Input {
+        #[validate(email)]
+        email: String,
+        #[validate]
+        item: Item,
+        #[validate]
+        items: Vec<Item>,
+    }
+
+    fn fields(input: &Input) -> Map<String, Value> {
+        let errors = input.validate().unwrap_err();
+        let mut fields = Map::new();
+        field_errors(&errors, None, Locale::default(), &mut fields);
+
+        fields
+    }
+
+    fn item(name: &str) -> Item {
+        Item {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn nested_fields_are_flattened() {
+        let input = Input {
+            email: "not an email".to_string(),
+            item: item(""),
+            items: vec![item("first"), item("")],
+        };
+
+        let fields = fields(&input);
+        let mut names: Vec<_> = fields.keys().map(String::as_str).collect();
+        names.sort_unstable();
+
+        assert_eq!(names, ["email", "item.name", "items[1].name"]);
+        assert_eq!(fields["email"][0]["code"], "email");
+        assert_eq!(fields["items[1].name"][0]["code"], "length");
+    }
+
+    #[test]
+    fn rejected_value_is_left_out() {
+        let input = Input {
+            email: "secret".to_string(),
+            item: item("name"),
+            items: Vec::new(),
+        };
+
+        let fields = fields(&input);
+        let params = fields["email"][0]["params"].as_object().unwrap();
+
+        assert!(!params.contains_key("value"));
+    }
+}
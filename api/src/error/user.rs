@@ -1,10 +1,90 @@
+use std::time::Duration;
+
 use axum::http::StatusCode;
 use thiserror::Error as ErrorTrait;
+use time::PrimitiveDateTime;
 
 #[derive(Debug, ErrorTrait)]
 pub(crate) enum PublicUserError {
     #[error("invalid credentials")]
     InvalidCredentials,
+
+    #[error("reauthentication required")]
+    ReauthenticationRequired,
+
+    #[error("reauthentication failed")]
+    ReauthenticationFailed,
+
+    #[error("invalid or expired token")]
+    InvalidToken,
+
+    #[error("email not verified")]
+    EmailNotVerified,
+
+    #[error("two-factor authentication already enabled")]
+    MfaAlreadyEnabled,
+
+    #[error("two-factor authentication not enrolled")]
+    MfaNotEnrolled,
+
+    #[error("invalid two-factor code")]
+    InvalidMfaCode,
+
+    #[error("invalid passkey")]
+    InvalidPasskey,
+
+    #[error("too many failed login attempts")]
+    TooManyAttempts(Duration),
+
+    #[error("account temporarily locked")]
+    AccountLocked(Duration),
+
+    #[error("account disabled")]
+    AccountDisabled(Option<String>),
+
+    #[error("account suspended")]
+    AccountSuspended(Option<String>, Option<PrimitiveDateTime>),
+
+    #[error("account banned")]
+    AccountBanned(Option<String>),
+
+    #[error("account scheduled for deletion")]
+    AccountDeleted,
+
+    #[error("already in use")]
+    Conflict(Vec<&'static str>),
+}
+
+impl PublicUserError {
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidCredentials => "invalid_credentials",
+            Self::ReauthenticationRequired => "reauthentication_required",
+            Self::ReauthenticationFailed => "reauthentication_failed",
+            Self::InvalidToken => "invalid_token",
+            Self::EmailNotVerified => "email_not_verified",
+            Self::MfaAlreadyEnabled => "mfa_already_enabled",
+            Self::MfaNotEnrolled => "mfa_not_enrolled",
+            Self::InvalidMfaCode => "invalid_mfa_code",
+            Self::InvalidPasskey => "invalid_passkey",
+            Self::TooManyAttempts(_) => "too_many_attempts",
+            Self::AccountLocked(_) => "account_locked",
+            Self::AccountDisabled(_) => "account_disabled",
+            Self::AccountSuspended(..) => "account_suspended",
+            Self::AccountBanned(_) => "account_banned",
+            Self::AccountDeleted => "account_deleted",
+            Self::Conflict(_) => "already_in_use",
+        }
+    }
+
+    /// When the request may be retried, sent along as `Retry-After`
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::TooManyAttempts(retry_after)
+            | Self::AccountLocked(retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, ErrorTrait)]
@@ -20,11 +100,84 @@ pub(crate) enum UserError {
 
     #[error("user not found")]
     NotFound,
+
+    #[error("reauthentication required")]
+    ReauthenticationRequired,
+
+    /// A wrong current password, the session itself is still valid
+    #[error("reauthentication failed")]
+    ReauthenticationFailed,
+
+    #[error("invalid or expired token")]
+    InvalidToken,
+
+    #[error("email not verified")]
+    EmailNotVerified,
+
+    #[error("two-factor authentication already enabled")]
+    MfaAlreadyEnabled,
+
+    #[error("two-factor authentication not enrolled")]
+    MfaNotEnrolled,
+
+    #[error("invalid two-factor code")]
+    InvalidMfaCode,
+
+    #[error("user has no passkey")]
+    NoPasskey,
+
+    #[error("login throttled for {0:?}")]
+    LoginThrottled(Duration),
+
+    #[error("account locked for {0:?}")]
+    AccountLocked(Duration),
+
+    /// Holds the reason the admin gave
+    #[error("account disabled")]
+    AccountDisabled(Option<String>),
+
+    /// Holds the reason and the end of the suspension
+    #[error("account suspended until {1:?}")]
+    AccountSuspended(Option<String>, Option<PrimitiveDateTime>),
+
+    #[error("account banned")]
+    AccountBanned(Option<String>),
+
+    /// Logging back in restores it until the grace period is over
+    #[error("account scheduled for deletion")]
+    AccountDeleted,
+
+    #[error("{0:?} already in use")]
+    Conflict(Vec<&'static str>),
 }
 
 impl From<UserError> for PublicUserError {
-    fn from(_err: UserError) -> Self {
-        Self::InvalidCredentials
+    fn from(err: UserError) -> Self {
+        match err {
+            UserError::ReauthenticationRequired => {
+                Self::ReauthenticationRequired
+            }
+            UserError::ReauthenticationFailed => Self::ReauthenticationFailed,
+            UserError::InvalidToken => Self::InvalidToken,
+            UserError::EmailNotVerified => Self::EmailNotVerified,
+            UserError::MfaAlreadyEnabled => Self::MfaAlreadyEnabled,
+            UserError::MfaNotEnrolled => Self::MfaNotEnrolled,
+            UserError::InvalidMfaCode => Self::InvalidMfaCode,
+            UserError::LoginThrottled(retry_after) => {
+                Self::TooManyAttempts(retry_after)
+            }
+            UserError::AccountLocked(retry_after) => {
+                Self::AccountLocked(retry_after)
+            }
+            UserError::AccountDisabled(reason) => Self::AccountDisabled(reason),
+            UserError::AccountSuspended(reason, until) => {
+                Self::AccountSuspended(reason, until)
+            }
+            UserError::AccountBanned(reason) => Self::AccountBanned(reason),
+            UserError::AccountDeleted => Self::AccountDeleted,
+            UserError::Conflict(fields) => Self::Conflict(fields),
+            _ => Self::InvalidCredentials,
+        }
     }
 }
 
@@ -32,6 +185,23 @@ impl From<&PublicUserError> for StatusCode {
     fn from(err: &PublicUserError) -> Self {
         match err {
             PublicUserError::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            PublicUserError::ReauthenticationRequired
+            | PublicUserError::ReauthenticationFailed => StatusCode::FORBIDDEN,
+            PublicUserError::InvalidToken => StatusCode::BAD_REQUEST,
+            PublicUserError::EmailNotVerified => StatusCode::FORBIDDEN,
+            PublicUserError::MfaAlreadyEnabled => StatusCode::CONFLICT,
+            PublicUserError::MfaNotEnrolled => StatusCode::BAD_REQUEST,
+            PublicUserError::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+            PublicUserError::InvalidPasskey => StatusCode::BAD_REQUEST,
+            PublicUserError::TooManyAttempts(_) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            PublicUserError::AccountLocked(_) => StatusCode::LOCKED,
+            PublicUserError::AccountDisabled(_)
+            | PublicUserError::AccountSuspended(..)
+            | PublicUserError::AccountBanned(_)
+            | PublicUserError::AccountDeleted => StatusCode::FORBIDDEN,
+            PublicUserError::Conflict(_) => StatusCode::CONFLICT,
         }
     }
 }
@@ -1,32 +1,216 @@
+use std::{
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+};
+
 use axum::{
-    async_trait, extract::FromRequestParts, http::request::Parts, TypedHeader,
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+    response::{IntoResponse, Response},
+    TypedHeader,
 };
+use axum_macros::{FromRequest, FromRequestParts};
 use headers::{authorization::Bearer, Authorization};
-use serde::Deserialize;
+use jsonwebtoken::errors::ErrorKind;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::env::{METRICS_TOKEN, TRUSTED_PROXIES},
+    dto::{
+        auth::{SubAccesToken, SubRefreshToken},
+        role::Permission,
+        user::User,
+    },
     error::{ApiError, ErrorRepr},
+    service::{role::RoleService, user::UserService},
     util::jwt::{self, ClaimsDecoded, ClaimsEncoded, ClaimsSubTrait},
+    AppState,
 };
 
 #[derive(Deserialize, Debug, Validate)]
 pub struct RequestUser {
-    #[validate(email(message = "must be a valid email"))]
+    #[validate(email)]
     pub username: String,
-    #[validate(length(min = 8, message = "must have at least 8 characters"))]
+    #[validate(length(min = 8))]
     pub password: String,
 }
 
-// TODO: Lose the async_strait
-// it is possible with lifetimes, boxes and an async scope
-#[async_trait]
-impl<S, T> FromRequestParts<S> for ClaimsDecoded<T>
+/// `axum::Json` with rejections rendered like every other error
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub(crate) struct Json<T>(pub(crate) T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// `axum::extract::Path` with rejections rendered like every other error
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub(crate) struct Path<T>(pub(crate) T);
+
+/// `axum::extract::Query` with rejections rendered like every other error
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub(crate) struct Query<T>(pub(crate) T);
+
+async fn decode_bearer<S, T>(
+    parts: &mut Parts,
+    state: &S,
+) -> Result<ClaimsDecoded<T>, ApiError>
 where
     S: Send + Sync,
     T: ClaimsSubTrait,
     jwt::Decoded<T>: for<'a> Deserialize<'a>,
 {
+    let TypedHeader(Authorization(bearer)) =
+        TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+            .await
+            .map_err(ErrorRepr::MissingBearer)?;
+
+    let token = bearer.token();
+    let claims: ClaimsEncoded<T> = From::from(token.to_owned());
+    let claims = claims.decode().map_err(ErrorRepr::InvalidBearer)?;
+
+    Ok(claims)
+}
+
+/// The access token and its user, rejected when the account was disabled,
+/// suspended or banned after the token was issued
+async fn active_user(
+    parts: &mut Parts,
+    state: &AppState,
+) -> Result<(ClaimsDecoded<SubAccesToken>, User), ApiError> {
+    let claims = decode_bearer::<_, SubAccesToken>(parts, state).await?;
+
+    let user =
+        UserService::get_by_uuid(claims.as_sub().user_uuid, &state.db).await?;
+    UserService::ensure_active(&user)?;
+    UserService::ensure_not_deleted(&user)?;
+
+    Ok((claims, user))
+}
+
+// TODO: Lose the async_strait
+// it is possible with lifetimes, boxes and an async scope
+#[async_trait]
+impl FromRequestParts<AppState> for ClaimsDecoded<SubAccesToken> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (claims, _) = active_user(parts, state).await?;
+
+        Ok(claims)
+    }
+}
+
+/// The session itself is checked by `UserService::verify_refresh_token`
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClaimsDecoded<SubRefreshToken> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        decode_bearer(parts, state).await
+    }
+}
+
+/// Address of the client, the peer itself unless `TRUSTED_PROXIES` are in
+/// front of the api
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClientIp(pub(crate) IpAddr);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        // Only missing when the server isn't started with connect info
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .ok_or(ApiError::Internal)?;
+
+        Ok(Self(client_ip(peer.ip(), &parts.headers, *TRUSTED_PROXIES)))
+    }
+}
+
+/// Each trusted proxy appends its peer to `X-Forwarded-For`, so the client
+/// is that many entries from the right. Anything further left was sent by
+/// the client and can't be trusted
+fn client_ip(
+    peer: IpAddr,
+    headers: &HeaderMap,
+    trusted_proxies: usize,
+) -> IpAddr {
+    let Some(hops) = trusted_proxies.checked_sub(1) else {
+        return peer;
+    };
+
+    let forwarded_for: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    forwarded_for
+        .iter()
+        .rev()
+        .nth(hops)
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(peer)
+}
+
+/// The uuid of the logged in user, rejected unless one of their roles grants
+/// `P`, e.g. `Guard<UsersManage>` for `permission = "users.manage"`
+#[derive(Debug)]
+pub(crate) struct Guard<P: Permission>(
+    pub(crate) Uuid,
+    pub(crate) PhantomData<P>,
+);
+
+#[async_trait]
+impl<P: Permission> FromRequestParts<AppState> for Guard<P> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (_, user) = active_user(parts, state).await?;
+
+        // Checked against the db, so revoking a role takes effect right away
+        let grants = RoleService::grants(user.uuid, &state.db).await?;
+        if !grants.allows::<P>() {
+            return Err(ApiError::Forbidden);
+        }
+
+        Ok(Self(user.uuid, PhantomData))
+    }
+}
+
+/// A scraper presenting the `METRICS_TOKEN`, so Prometheus doesn't need a
+/// user session it can't refresh
+#[derive(Debug)]
+pub(crate) struct ScrapeToken;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ScrapeToken {
     type Rejection = ApiError;
 
     async fn from_request_parts(
@@ -40,10 +224,76 @@ where
             .await
             .map_err(ErrorRepr::MissingBearer)?;
 
-        let token = bearer.token();
-        let claims: ClaimsEncoded<T> = From::from(token.to_owned());
-        let claims = claims.decode()?;
+        if !scrape_token_matches(METRICS_TOKEN.as_deref(), bearer.token()) {
+            return Err(ErrorRepr::InvalidBearer(
+                ErrorKind::InvalidToken.into(),
+            )
+            .into());
+        }
 
-        Ok(claims)
+        Ok(Self)
+    }
+}
+
+/// Compares digests so the time taken doesn't leak how much of the token
+/// was right
+fn scrape_token_matches(expected: Option<&str>, token: &str) -> bool {
+    expected.is_some_and(|expected| {
+        Sha256::digest(expected.as_bytes()) == Sha256::digest(token.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn forwarded_for(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_static(value));
+        }
+
+        headers
+    }
+
+    #[test]
+    fn client_ip_is_the_peer_without_trusted_proxies() {
+        let peer = IpAddr::from([10, 0, 0, 1]);
+        let headers = forwarded_for(&["1.1.1.1"]);
+
+        assert_eq!(client_ip(peer, &headers, 0), peer);
+    }
+
+    #[test]
+    fn client_ip_skips_the_trusted_proxies() {
+        let peer = IpAddr::from([10, 0, 0, 1]);
+        let headers = forwarded_for(&["6.6.6.6, 1.1.1.1", "10.0.0.2"]);
+
+        assert_eq!(client_ip(peer, &headers, 1), IpAddr::from([10, 0, 0, 2]));
+        assert_eq!(client_ip(peer, &headers, 2), IpAddr::from([1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn client_ip_falls_back_to_the_peer() {
+        let peer = IpAddr::from([10, 0, 0, 1]);
+
+        assert_eq!(client_ip(peer, &HeaderMap::new(), 1), peer);
+        assert_eq!(client_ip(peer, &forwarded_for(&["1.1.1.1"]), 2), peer);
+        assert_eq!(client_ip(peer, &forwarded_for(&["unknown"]), 1), peer);
+    }
+
+    #[test]
+    fn scrape_token_must_match() {
+        assert!(scrape_token_matches(Some("secret"), "secret"));
+        assert!(!scrape_token_matches(Some("secret"), "secreT"));
+        assert!(!scrape_token_matches(Some("secret"), ""));
+    }
+
+    #[test]
+    fn scrape_token_is_rejected_when_unset() {
+        assert!(!scrape_token_matches(None, ""));
+        assert!(!scrape_token_matches(None, "secret"));
     }
 }
@@ -1,2 +1,8 @@
+pub(crate) mod admin;
 pub(crate) mod auth;
+pub(crate) mod avatar;
+pub(crate) mod metrics;
+pub(crate) mod mfa;
+pub(crate) mod passkey;
+pub(crate) mod session;
 pub(crate) mod user;
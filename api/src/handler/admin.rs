@@ -0,0 +1,139 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post, put},
+    Router,
+};
+use uuid::Uuid;
+
+use crate::{
+    dto::{
+        admin::{
+            AdminStatusInput, AdminUpdateUserInput, AdminUserFilter,
+            AdminUserPayload, AdminUsersPayload,
+        },
+        role::{Role, RolesManage, UsersManage},
+    },
+    error::ApiResult,
+    extractor::{Guard, Json, Path, Query},
+    service::{admin::AdminService, role::RoleService},
+    util::validate_payload,
+    AppState,
+};
+
+/// User management, only for admins
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list))
+        .route("/:uuid", get(get_user).patch(update))
+        .route("/:uuid/status", put(set_status))
+        .route("/:uuid/logout", post(logout))
+        .route("/:uuid/password-reset", post(reset_password))
+        .route("/:uuid/roles/:role", put(assign_role).delete(unassign_role))
+}
+
+/// Roles and their permissions, only for admins
+pub(crate) fn roles() -> Router<AppState> {
+    Router::new().route("/", get(list_roles))
+}
+
+async fn list(
+    State(state): State<AppState>,
+    _guard: Guard<UsersManage>,
+    Query(filter): Query<AdminUserFilter>,
+) -> ApiResult<Json<AdminUsersPayload>> {
+    validate_payload(&filter)?;
+
+    let users = AdminService::list(filter, &state.db).await?;
+
+    Ok(Json(users))
+}
+
+async fn get_user(
+    State(state): State<AppState>,
+    _guard: Guard<UsersManage>,
+    Path(uuid): Path<Uuid>,
+) -> ApiResult<Json<AdminUserPayload>> {
+    let user = AdminService::get(uuid, &state.db).await?;
+
+    Ok(Json(user))
+}
+
+async fn update(
+    State(state): State<AppState>,
+    _guard: Guard<UsersManage>,
+    Path(uuid): Path<Uuid>,
+    Json(input): Json<AdminUpdateUserInput>,
+) -> ApiResult<Json<AdminUserPayload>> {
+    validate_payload(&input)?;
+
+    let user =
+        AdminService::update(uuid, input, state.mailer.as_ref(), &state.db)
+            .await?;
+
+    Ok(Json(user))
+}
+
+async fn set_status(
+    State(state): State<AppState>,
+    Guard(admin_uuid, _): Guard<UsersManage>,
+    Path(uuid): Path<Uuid>,
+    Json(input): Json<AdminStatusInput>,
+) -> ApiResult<Json<AdminUserPayload>> {
+    validate_payload(&input)?;
+
+    let user =
+        AdminService::set_status(admin_uuid, uuid, input, &state.db).await?;
+
+    Ok(Json(user))
+}
+
+async fn logout(
+    State(state): State<AppState>,
+    _guard: Guard<UsersManage>,
+    Path(uuid): Path<Uuid>,
+) -> ApiResult<()> {
+    AdminService::logout(uuid, &state.db).await?;
+
+    Ok(())
+}
+
+async fn reset_password(
+    State(state): State<AppState>,
+    _guard: Guard<UsersManage>,
+    Path(uuid): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    AdminService::reset_password(uuid, state.mailer.as_ref(), &state.db)
+        .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn assign_role(
+    State(state): State<AppState>,
+    _guard: Guard<RolesManage>,
+    Path((uuid, role)): Path<(Uuid, String)>,
+) -> ApiResult<()> {
+    RoleService::assign(uuid, &role, &state.db).await?;
+
+    Ok(())
+}
+
+async fn unassign_role(
+    State(state): State<AppState>,
+    Guard(admin_uuid, _): Guard<RolesManage>,
+    Path((uuid, role)): Path<(Uuid, String)>,
+) -> ApiResult<()> {
+    RoleService::unassign(admin_uuid, uuid, &role, &state.db).await?;
+
+    Ok(())
+}
+
+async fn list_roles(
+    State(state): State<AppState>,
+    _guard: Guard<RolesManage>,
+) -> ApiResult<Json<Vec<Role>>> {
+    let roles = RoleService::list(&state.db).await?;
+
+    Ok(Json(roles))
+}
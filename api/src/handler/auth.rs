@@ -2,49 +2,180 @@ use std::time::Duration;
 
 use axum::{
     extract::State,
+    http::{header::USER_AGENT, HeaderMap, StatusCode},
     routing::{get, post},
-    Json, Router,
+    Router,
 };
 
+use super::passkey;
 use crate::{
     config::constant::{BEARER, REFRESH_TOKEN_TIMEOUT},
     dto::{
-        auth::{LoginPayload, RefreshPayload, SubAccesToken, SubRefreshToken},
-        user::LoginUserInput,
+        auth::{
+            ForgotPasswordInput, LoginPayload, LoginResponse, LoginStep,
+            MagicLinkInput, MagicLinkLoginInput, RefreshPayload,
+            ResetPasswordInput, SubAccesToken, SubRefreshToken,
+        },
+        mfa::{MfaEmailInput, MfaLoginInput, MfaPendingPayload},
+        user::{LoginUserInput, User},
     },
     error::ApiResult,
-    service::user::UserService,
+    extractor::{ClientIp, Json},
+    service::{
+        mfa::MfaService, role::RoleService, session::SessionService,
+        user::UserService,
+    },
     util::{
         jwt::{self, Claims, ClaimsDecoded},
         validate_payload,
     },
-    AppState,
+    AppState, DbConn,
 };
 
 pub(crate) fn routes() -> Router<AppState> {
     Router::new()
         .route("/login", post(login))
+        .route("/login/magic", post(login_magic_link))
+        .route("/login/mfa", post(login_mfa))
+        .route("/login/mfa/email", post(send_mfa_email_code))
+        .route("/magic-link", post(request_magic_link))
         .route("/logout", post(logout))
         .route("/refresh", post(refresh))
         .route("/me", get(me))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .merge(passkey::login_routes())
 }
 
 async fn login(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
     Json(input): Json<LoginUserInput>,
+) -> ApiResult<Json<LoginResponse>> {
+    validate_payload(&input)?;
+
+    let login_step = UserService::login(
+        input,
+        ip,
+        &state.login_throttle,
+        state.mailer.as_ref(),
+        &state.db,
+    )
+    .await?;
+
+    let login_response = login_response(login_step, &state.db).await?;
+
+    Ok(Json(login_response))
+}
+
+async fn login_mfa(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(input): Json<MfaLoginInput>,
 ) -> ApiResult<Json<LoginPayload>> {
+    let remember_device = input.remember_device;
+    let user =
+        MfaService::login(input, ip, &state.login_throttle, &state.db).await?;
+
+    let login_payload =
+        mfa_login_payload(&user, remember_device, &headers, &state.db).await?;
+
+    Ok(Json(login_payload))
+}
+
+async fn send_mfa_email_code(
+    State(state): State<AppState>,
+    Json(input): Json<MfaEmailInput>,
+) -> ApiResult<()> {
+    MfaService::resend_email_code(
+        input.mfa_token,
+        state.mailer.as_ref(),
+        &state.db,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn request_magic_link(
+    State(state): State<AppState>,
+    Json(input): Json<MagicLinkInput>,
+) -> ApiResult<StatusCode> {
     validate_payload(&input)?;
 
-    let uuid = UserService::login(input, &state.db).await?;
+    // Handled in the background so the response (time) does not reveal if
+    // the email is registered
+    tokio::spawn(async move {
+        let res = UserService::request_magic_link(
+            input.email,
+            state.mailer.as_ref(),
+            &state.db,
+        )
+        .await;
+
+        if let Err(err) = res {
+            tracing::error!("failed to send magic link: {:?}", err);
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn login_magic_link(
+    State(state): State<AppState>,
+    Json(input): Json<MagicLinkLoginInput>,
+) -> ApiResult<Json<LoginResponse>> {
+    let login_step = UserService::login_magic_link(
+        input.token,
+        input.trusted_device_token,
+        state.mailer.as_ref(),
+        &state.db,
+    )
+    .await?;
+
+    let login_response = login_response(login_step, &state.db).await?;
+
+    Ok(Json(login_response))
+}
+
+async fn login_response(
+    login_step: LoginStep,
+    db: &DbConn,
+) -> ApiResult<LoginResponse> {
+    let login_response = match login_step {
+        LoginStep::Complete(user) => {
+            LoginResponse::Complete(login_payload(&user, db).await?)
+        }
+        LoginStep::MfaRequired(uuid, methods) => {
+            LoginResponse::MfaRequired(MfaPendingPayload {
+                mfa_token: MfaService::issue_token(uuid, db).await?,
+                methods,
+            })
+        }
+    };
+
+    Ok(login_response)
+}
+
+/// Starts a new session for a user that has been authenticated
+pub(crate) async fn login_payload(
+    user: &User,
+    db: &DbConn,
+) -> ApiResult<LoginPayload> {
     let refresh_token = UserService::create_refresh_token(
-        uuid,
+        user.uuid,
         Duration::from_secs(REFRESH_TOKEN_TIMEOUT),
-        &state.db,
+        db,
     )
     .await?;
 
+    let grants = RoleService::grants(user.uuid, db).await?;
+
+    // The access token carries the locale for requests that don't ask for one
     let sub_refresh_token = SubRefreshToken::new(refresh_token.token);
-    let sub_access_token = SubAccesToken::new(uuid);
+    let sub_access_token =
+        SubAccesToken::new(user.uuid, user.locale, grants, refresh_token.id);
 
     let claim_refresh_token = Claims::new(sub_refresh_token)?;
     let claim_access_token = Claims::new(sub_access_token)?;
@@ -55,9 +186,34 @@ async fn login(
             access_token: claim_access_token,
             token_type: BEARER.to_string(),
         },
+        trusted_device_token: None,
     };
 
-    Ok(Json(login_payload))
+    Ok(login_payload)
+}
+
+/// Starts a new session once the second factor has been verified, trusting
+/// the device for the next logins when asked to
+pub(crate) async fn mfa_login_payload(
+    user: &User,
+    remember_device: bool,
+    headers: &HeaderMap,
+    db: &DbConn,
+) -> ApiResult<LoginPayload> {
+    let mut login_payload = login_payload(user, db).await?;
+
+    if remember_device {
+        let name = headers
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        let trusted_device_token =
+            SessionService::trust_device(user, name, db).await?;
+        login_payload.trusted_device_token = Some(trusted_device_token);
+    }
+
+    Ok(login_payload)
 }
 
 async fn logout(
@@ -73,11 +229,17 @@ async fn refresh(
     State(state): State<AppState>,
     claims: ClaimsDecoded<SubRefreshToken>,
 ) -> ApiResult<Json<RefreshPayload>> {
-    let (_refresh_token, user) =
+    let (refresh_token, user) =
         UserService::verify_refresh_token(claims.sub().token, &state.db)
             .await?;
 
-    let claim_access_token = Claims::new(SubAccesToken::new(user.uuid))?;
+    let grants = RoleService::grants(user.uuid, &state.db).await?;
+    let claim_access_token = Claims::new(SubAccesToken::new(
+        user.uuid,
+        user.locale,
+        grants,
+        refresh_token.id,
+    ))?;
 
     let refresh_payload = RefreshPayload {
         access_token: claim_access_token,
@@ -93,3 +255,38 @@ async fn me(
 ) -> ApiResult<Json<jwt::Decoded<SubAccesToken>>> {
     Ok(Json(claims.claims()))
 }
+
+async fn forgot_password(
+    State(state): State<AppState>,
+    Json(input): Json<ForgotPasswordInput>,
+) -> ApiResult<StatusCode> {
+    validate_payload(&input)?;
+
+    // Handled in the background so the response (time) does not reveal if
+    // the email is registered
+    tokio::spawn(async move {
+        let res = UserService::forgot_password(
+            input.email,
+            state.mailer.as_ref(),
+            &state.db,
+        )
+        .await;
+
+        if let Err(err) = res {
+            tracing::error!("failed to send password reset: {:?}", err);
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn reset_password(
+    State(state): State<AppState>,
+    Json(input): Json<ResetPasswordInput>,
+) -> ApiResult<()> {
+    validate_payload(&input)?;
+
+    UserService::reset_password(input, &state.db).await?;
+
+    Ok(())
+}
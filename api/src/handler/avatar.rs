@@ -0,0 +1,97 @@
+use axum::{
+    extract::{
+        multipart::MultipartRejection, DefaultBodyLimit, Multipart, State,
+    },
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderValue,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Router,
+};
+
+use crate::{
+    config::constant::{AVATAR_CACHE_MAX_AGE, AVATAR_MAX_SIZE},
+    dto::{auth::SubAccesToken, user::AvatarPayload},
+    error::{ApiError, ApiResult},
+    extractor::{Json, Path},
+    service::avatar::AvatarService,
+    util::jwt::ClaimsDecoded,
+    AppState,
+};
+
+/// Avatar of the logged in user
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", put(upload))
+        // Leaves room for the multipart framing around the image
+        .layer(DefaultBodyLimit::max(AVATAR_MAX_SIZE + 64 * 1024))
+}
+
+/// The stored thumbnails, at the urls handed out on upload
+pub(crate) fn files() -> Router<AppState> {
+    Router::new().route("/avatars/:hash/:file", get(serve))
+}
+
+async fn upload(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> ApiResult<Json<AvatarPayload>> {
+    let bytes = read_avatar(multipart?).await?;
+
+    let avatar = AvatarService::upload(
+        claims.sub().user_uuid,
+        bytes,
+        state.storage.as_ref(),
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(avatar))
+}
+
+/// Reads the `avatar` field, refusing it once it grows past the limit
+async fn read_avatar(mut multipart: Multipart) -> ApiResult<Vec<u8>> {
+    while let Some(mut field) = multipart.next_field().await? {
+        if field.name() != Some("avatar") {
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.chunk().await? {
+            if bytes.len() + chunk.len() > AVATAR_MAX_SIZE {
+                return Err(ApiError::ImageTooLarge(AVATAR_MAX_SIZE));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        return Ok(bytes);
+    }
+
+    Err(ApiError::MissingAvatar)
+}
+
+async fn serve(
+    State(state): State<AppState>,
+    Path((hash, file)): Path<(String, String)>,
+) -> ApiResult<Response> {
+    let png = AvatarService::get(&hash, &file, state.storage.as_ref())
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    // The url changes with the content, so it can be cached forever
+    let cache_control =
+        format!("public, max-age={AVATAR_CACHE_MAX_AGE}, immutable");
+    let headers = [
+        (CONTENT_TYPE, HeaderValue::from_static("image/png")),
+        (
+            CACHE_CONTROL,
+            HeaderValue::from_str(&cache_control)
+                .expect("cache control is a valid header"),
+        ),
+    ];
+
+    Ok((headers, png).into_response())
+}
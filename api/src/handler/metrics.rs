@@ -0,0 +1,22 @@
+use axum::{routing::get, Router};
+
+use crate::{extractor::ScrapeToken, util::encryption::HASHING_POOL, AppState};
+
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(metrics))
+}
+
+/// Prometheus text exposition format, the scraper authenticates with the
+/// `METRICS_TOKEN`
+async fn metrics(_token: ScrapeToken) -> String {
+    format!(
+        "# HELP password_hashing_queue_depth Password hashing jobs queued or running\n\
+         # TYPE password_hashing_queue_depth gauge\n\
+         password_hashing_queue_depth {}\n\
+         # HELP password_hashing_queue_capacity Password hashing jobs accepted before rejecting\n\
+         # TYPE password_hashing_queue_capacity gauge\n\
+         password_hashing_queue_capacity {}\n",
+        HASHING_POOL.depth(),
+        HASHING_POOL.capacity(),
+    )
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Router,
+};
+
+use crate::{
+    dto::{
+        auth::SubAccesToken,
+        mfa::{
+            ConfirmTotpInput, MfaSettingsPayload, RecoveryCodesPayload,
+            TotpEnrollmentPayload, UpdateMfaSettingsInput,
+        },
+        user::ReauthenticateInput,
+    },
+    error::ApiResult,
+    extractor::Json,
+    service::mfa::MfaService,
+    util::{jwt::ClaimsDecoded, validate_payload},
+    AppState,
+};
+
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(settings).patch(update_settings))
+        .route("/totp", post(enroll_totp).delete(disable_totp))
+        .route("/totp/confirm", post(confirm_totp))
+}
+
+async fn settings(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+) -> ApiResult<Json<MfaSettingsPayload>> {
+    let payload =
+        MfaService::settings(claims.sub().user_uuid, &state.db).await?;
+
+    Ok(Json(payload))
+}
+
+async fn update_settings(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<UpdateMfaSettingsInput>,
+) -> ApiResult<Json<MfaSettingsPayload>> {
+    let payload = MfaService::update_settings(
+        &claims.claims(),
+        input,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(payload))
+}
+
+async fn enroll_totp(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<ReauthenticateInput>,
+) -> ApiResult<Json<TotpEnrollmentPayload>> {
+    validate_payload(&input)?;
+
+    let payload = MfaService::enroll_totp(
+        &claims.claims(),
+        input.current_password,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(payload))
+}
+
+async fn confirm_totp(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<ConfirmTotpInput>,
+) -> ApiResult<Json<RecoveryCodesPayload>> {
+    validate_payload(&input)?;
+
+    let recovery_codes =
+        MfaService::confirm_totp(claims.sub().user_uuid, input.code, &state.db)
+            .await?;
+
+    Ok(Json(RecoveryCodesPayload { recovery_codes }))
+}
+
+async fn disable_totp(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<ReauthenticateInput>,
+) -> ApiResult<()> {
+    validate_payload(&input)?;
+
+    MfaService::disable_totp(
+        &claims.claims(),
+        input.current_password,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    routing::{delete, get, post},
+    Router,
+};
+
+use super::auth::{login_payload, mfa_login_payload};
+use crate::{
+    dto::{
+        auth::{LoginPayload, SubAccesToken},
+        passkey::{
+            FinishPasskeyLoginInput, FinishPasskeyMfaInput,
+            FinishPasskeyRegistrationInput, PasskeyAuthenticationPayload,
+            PasskeyLoginInput, PasskeyMfaInput, PasskeyPayload,
+            PasskeyRegistrationPayload,
+        },
+        user::ReauthenticateInput,
+    },
+    error::ApiResult,
+    extractor::{ClientIp, Json, Path},
+    service::passkey::PasskeyService,
+    util::{jwt::ClaimsDecoded, validate_payload},
+    AppState,
+};
+
+/// Managing the passkeys of the logged in user
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list))
+        .route("/:id", delete(remove))
+        .route("/register/start", post(start_registration))
+        .route("/register/finish", post(finish_registration))
+}
+
+/// Logging in with a passkey, as first or second factor
+pub(crate) fn login_routes() -> Router<AppState> {
+    Router::new()
+        .route("/passkey/start", post(start_login))
+        .route("/passkey/finish", post(finish_login))
+        .route("/login/mfa/passkey/start", post(start_mfa))
+        .route("/login/mfa/passkey/finish", post(finish_mfa))
+}
+
+async fn list(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+) -> ApiResult<Json<Vec<PasskeyPayload>>> {
+    let passkeys =
+        PasskeyService::list(claims.sub().user_uuid, &state.db).await?;
+
+    Ok(Json(passkeys))
+}
+
+async fn remove(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Path(id): Path<i32>,
+    Json(input): Json<ReauthenticateInput>,
+) -> ApiResult<()> {
+    validate_payload(&input)?;
+
+    PasskeyService::delete(
+        &claims.claims(),
+        id,
+        input.current_password,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn start_registration(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<ReauthenticateInput>,
+) -> ApiResult<Json<PasskeyRegistrationPayload>> {
+    validate_payload(&input)?;
+
+    let payload = PasskeyService::start_registration(
+        &state.passkey,
+        &claims.claims(),
+        input.current_password,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(payload))
+}
+
+async fn finish_registration(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<FinishPasskeyRegistrationInput>,
+) -> ApiResult<Json<PasskeyPayload>> {
+    validate_payload(&input)?;
+
+    let passkey = PasskeyService::finish_registration(
+        &state.passkey,
+        claims.sub().user_uuid,
+        input,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(passkey))
+}
+
+async fn start_login(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(input): Json<PasskeyLoginInput>,
+) -> ApiResult<Json<PasskeyAuthenticationPayload>> {
+    validate_payload(&input)?;
+
+    let payload = PasskeyService::start_login(
+        &state.passkey,
+        input.email,
+        ip,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(payload))
+}
+
+async fn finish_login(
+    State(state): State<AppState>,
+    Json(input): Json<FinishPasskeyLoginInput>,
+) -> ApiResult<Json<LoginPayload>> {
+    let user = PasskeyService::finish_login(
+        &state.passkey,
+        input.ceremony_id,
+        input.credential,
+        &state.db,
+    )
+    .await?;
+
+    let login_payload = login_payload(&user, &state.db).await?;
+
+    Ok(Json(login_payload))
+}
+
+async fn start_mfa(
+    State(state): State<AppState>,
+    Json(input): Json<PasskeyMfaInput>,
+) -> ApiResult<Json<PasskeyAuthenticationPayload>> {
+    let payload =
+        PasskeyService::start_mfa(&state.passkey, input.mfa_token, &state.db)
+            .await?;
+
+    Ok(Json(payload))
+}
+
+async fn finish_mfa(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(input): Json<FinishPasskeyMfaInput>,
+) -> ApiResult<Json<LoginPayload>> {
+    let user = PasskeyService::finish_mfa(
+        &state.passkey,
+        input.mfa_token,
+        input.ceremony_id,
+        input.credential,
+        &state.db,
+    )
+    .await?;
+
+    let login_payload =
+        mfa_login_payload(&user, input.remember_device, &headers, &state.db)
+            .await?;
+
+    Ok(Json(login_payload))
+}
@@ -0,0 +1,70 @@
+use axum::{
+    extract::State,
+    routing::{delete, get},
+    Router,
+};
+use uuid::Uuid;
+
+use crate::{
+    dto::{auth::SubAccesToken, session::SessionsPayload},
+    error::ApiResult,
+    extractor::{Json, Path},
+    service::session::SessionService,
+    util::jwt::ClaimsDecoded,
+    AppState,
+};
+
+/// Sessions and trusted devices of the logged in user
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list))
+        .route("/:id", delete(revoke_session))
+        .route("/devices", delete(revoke_trusted_devices))
+        .route("/devices/:uuid", delete(revoke_trusted_device))
+}
+
+async fn list(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+) -> ApiResult<Json<SessionsPayload>> {
+    let sessions =
+        SessionService::list(claims.sub().user_uuid, &state.db).await?;
+
+    Ok(Json(sessions))
+}
+
+async fn revoke_session(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Path(id): Path<i32>,
+) -> ApiResult<()> {
+    SessionService::revoke_session(claims.sub().user_uuid, id, &state.db)
+        .await?;
+
+    Ok(())
+}
+
+async fn revoke_trusted_devices(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+) -> ApiResult<()> {
+    SessionService::revoke_trusted_devices(claims.sub().user_uuid, &state.db)
+        .await?;
+
+    Ok(())
+}
+
+async fn revoke_trusted_device(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Path(uuid): Path<Uuid>,
+) -> ApiResult<()> {
+    SessionService::revoke_trusted_device(
+        claims.sub().user_uuid,
+        uuid,
+        &state.db,
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,27 +1,53 @@
+use std::time::Duration;
+
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{
+        header::{CONTENT_DISPOSITION, RETRY_AFTER},
+        HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Router,
 };
 use axum_macros::debug_handler;
+use uuid::Uuid;
 
-use crate::{dto::user::UpdateUserInput, util::jwt::ClaimsDecoded};
+use super::{avatar, mfa, passkey, session};
 use crate::{
+    config::constant::{USER_EXPORT_INLINE_WAIT, USER_EXPORT_RETRY_AFTER},
     dto::{
         auth::SubAccesToken,
-        user::{RegisterUserInput, User},
+        user::{
+            AccountDeletionPayload, PublicUserPayload, ReauthenticateInput,
+            RegisterUserInput, ResendEmailVerificationInput, User,
+            UserIdentifier, VerifyEmailInput,
+        },
     },
     error::ApiResult,
-    service::user::UserService,
+    extractor::{Json, Path},
+    service::{
+        export::{ExportService, ExportStatus},
+        user::UserService,
+    },
     util::validate_payload,
     AppState,
 };
+use crate::{dto::user::UpdateUserInput, util::jwt::ClaimsDecoded};
 
 pub(crate) fn routes() -> Router<AppState> {
     Router::new()
         .route("/", post(register))
-        .route("/me", get(me).patch(update))
+        .route("/me", get(me).patch(update).delete(delete))
+        .route("/me/export", get(export))
+        .route("/:uuid", get(profile_by_uuid))
+        .route("/by-name/:display_name", get(profile_by_name))
+        .route("/verify-email", post(verify_email))
+        .route("/verify-email/resend", post(resend_verification_email))
+        .nest("/me/avatar", avatar::routes())
+        .nest("/me/mfa", mfa::routes())
+        .nest("/me/passkeys", passkey::routes())
+        .nest("/me/sessions", session::routes())
 }
 
 async fn register(
@@ -30,7 +56,18 @@ async fn register(
 ) -> ApiResult<StatusCode> {
     validate_payload(&input)?;
 
-    let _uuid = UserService::register_user(input, &state.db).await?;
+    let user = UserService::register_user(input, &state.db).await?;
+
+    // The account exists at this point, a new link can be requested
+    let res = UserService::send_email_verification(
+        &user,
+        state.mailer.as_ref(),
+        &state.db,
+    )
+    .await;
+    if let Err(err) = res {
+        tracing::error!("failed to send email verification: {:?}", err);
+    }
 
     Ok(StatusCode::CREATED)
 }
@@ -43,9 +80,14 @@ async fn update(
 ) -> ApiResult<()> {
     validate_payload(&input)?;
 
-    let user_uuid = claims.sub().user_uuid;
-
-    UserService::update_by_uuid(user_uuid, input, &state.db).await?;
+    UserService::update_by_uuid(
+        &claims.claims(),
+        input,
+        &state.login_throttle,
+        state.mailer.as_ref(),
+        &state.db,
+    )
+    .await?;
 
     Ok(())
 }
@@ -59,3 +101,142 @@ async fn me(
 
     Ok(Json(user))
 }
+
+async fn delete(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+    Json(input): Json<ReauthenticateInput>,
+) -> ApiResult<(StatusCode, Json<AccountDeletionPayload>)> {
+    let purge_at = UserService::delete(
+        &claims.claims(),
+        input.current_password,
+        &state.login_throttle,
+        &state.db,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(AccountDeletionPayload { purge_at }),
+    ))
+}
+
+async fn profile_by_uuid(
+    State(state): State<AppState>,
+    claims: Option<ClaimsDecoded<SubAccesToken>>,
+    Path(uuid): Path<Uuid>,
+) -> ApiResult<Json<PublicUserPayload>> {
+    let caller = claims.map(|claims| claims.sub().user_uuid);
+    let profile =
+        UserService::profile(UserIdentifier::Uuid(uuid), caller, &state.db)
+            .await?;
+
+    Ok(Json(profile))
+}
+
+async fn profile_by_name(
+    State(state): State<AppState>,
+    claims: Option<ClaimsDecoded<SubAccesToken>>,
+    Path(display_name): Path<String>,
+) -> ApiResult<Json<PublicUserPayload>> {
+    let caller = claims.map(|claims| claims.sub().user_uuid);
+    let profile = UserService::profile(
+        UserIdentifier::DisplayName(display_name),
+        caller,
+        &state.db,
+    )
+    .await?;
+
+    Ok(Json(profile))
+}
+
+/// Serves the export when it's ready, otherwise starts generating it and
+/// waits a little so small accounts get theirs right away
+async fn export(
+    State(state): State<AppState>,
+    claims: ClaimsDecoded<SubAccesToken>,
+) -> ApiResult<Response> {
+    let user_uuid = claims.sub().user_uuid;
+
+    let status = match state.exports.take(user_uuid) {
+        ExportStatus::Missing if state.exports.start(user_uuid) => {
+            let exports = state.exports.clone();
+            let generate = tokio::spawn(async move {
+                let db = state.db.clone();
+                let job = tokio::spawn(async move {
+                    ExportService::generate(user_uuid, &db).await
+                });
+
+                // A panic must not leave the export pending forever
+                let export = match job.await {
+                    Ok(export) => export,
+                    Err(err) => Err(err.into()),
+                };
+                state.exports.finish(user_uuid, export);
+            });
+
+            let wait = Duration::from_secs(USER_EXPORT_INLINE_WAIT);
+            let _ = tokio::time::timeout(wait, generate).await;
+
+            exports.take(user_uuid)
+        }
+        status => status,
+    };
+
+    let response = match status {
+        ExportStatus::Ready(export) => (
+            [(
+                CONTENT_DISPOSITION,
+                HeaderValue::from_static(
+                    "attachment; filename=\"export.json\"",
+                ),
+            )],
+            Json(export),
+        )
+            .into_response(),
+        ExportStatus::Pending | ExportStatus::Missing => (
+            StatusCode::ACCEPTED,
+            [(RETRY_AFTER, HeaderValue::from(USER_EXPORT_RETRY_AFTER))],
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
+
+async fn verify_email(
+    State(state): State<AppState>,
+    Json(input): Json<VerifyEmailInput>,
+) -> ApiResult<()> {
+    validate_payload(&input)?;
+
+    UserService::verify_email(input.token, &state.db).await?;
+
+    Ok(())
+}
+
+/// Doesn't need a login, unverified users can't log in when verification is
+/// required
+async fn resend_verification_email(
+    State(state): State<AppState>,
+    Json(input): Json<ResendEmailVerificationInput>,
+) -> ApiResult<StatusCode> {
+    validate_payload(&input)?;
+
+    // Handled in the background so the response (time) does not reveal if
+    // the email is registered
+    tokio::spawn(async move {
+        let res = UserService::resend_email_verification(
+            input.email,
+            state.mailer.as_ref(),
+            &state.db,
+        )
+        .await;
+
+        if let Err(err) = res {
+            tracing::error!("failed to resend email verification: {:?}", err);
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
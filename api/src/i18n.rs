@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+mod en;
+mod nl;
+
+/// Languages with a message catalog
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Locale {
+    #[default]
+    En,
+    Nl,
+}
+
+impl Locale {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Nl => "nl",
+        }
+    }
+
+    /// Matches on the primary language subtag, `nl-BE` is `nl`
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split('-').next()?.trim();
+
+        if language.eq_ignore_ascii_case("en") {
+            Some(Locale::En)
+        } else if language.eq_ignore_ascii_case("nl") {
+            Some(Locale::Nl)
+        } else {
+            None
+        }
+    }
+
+    /// The supported language the client prefers most, if any
+    pub(crate) fn from_accept_language(accept_language: &str) -> Option<Self> {
+        let mut languages: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|language| {
+                let mut parts = language.split(';');
+                let locale = Self::from_tag(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |quality| quality.parse().ok())?;
+
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+
+        // Stable, so equal qualities keep the order of the header
+        languages.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        languages.first().map(|(_, locale)| *locale)
+    }
+
+    /// Message for an error code
+    pub(crate) fn error(&self, code: &str) -> Option<&'static str> {
+        self.errors()
+            .iter()
+            .find(|(known, _)| *known == code)
+            .map(|(_, message)| *message)
+    }
+
+    fn errors(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => en::ERRORS,
+            Locale::Nl => nl::ERRORS,
+        }
+    }
+
+    /// Message for a failed validator, by its code and params
+    pub(crate) fn validation(
+        &self,
+        code: &str,
+        params: &Map<String, Value>,
+    ) -> Option<String> {
+        match self {
+            Locale::En => en::validation(code, params),
+            Locale::Nl => nl::validation(code, params),
+        }
+    }
+
+    /// For validators without a message of their own
+    pub(crate) fn invalid_field(&self) -> &'static str {
+        match self {
+            Locale::En => en::INVALID_FIELD,
+            Locale::Nl => nl::INVALID_FIELD,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn codes(locale: Locale) -> BTreeSet<&'static str> {
+        locale.errors().iter().map(|(code, _)| *code).collect()
+    }
+
+    #[test]
+    fn catalogs_cover_the_same_codes() {
+        assert_eq!(codes(Locale::En), codes(Locale::Nl));
+        assert_eq!(codes(Locale::En).len(), en::ERRORS.len());
+        assert_eq!(codes(Locale::Nl).len(), nl::ERRORS.len());
+    }
+
+    #[test]
+    fn highest_quality_wins() {
+        assert_eq!(
+            Locale::from_accept_language("en;q=0.5, nl-BE;q=0.9"),
+            Some(Locale::Nl)
+        );
+        assert_eq!(
+            Locale::from_accept_language("nl;q=0.8,en"),
+            Some(Locale::En)
+        );
+    }
+
+    #[test]
+    fn header_order_breaks_ties() {
+        assert_eq!(Locale::from_accept_language("nl, en"), Some(Locale::Nl));
+        assert_eq!(Locale::from_accept_language("en, nl"), Some(Locale::En));
+    }
+
+    #[test]
+    fn unsupported_and_refused_languages_are_skipped() {
+        assert_eq!(
+            Locale::from_accept_language("fr-FR, de;q=0.9, nl;q=0.1"),
+            Some(Locale::Nl)
+        );
+        assert_eq!(Locale::from_accept_language("nl;q=0, fr"), None);
+        assert_eq!(Locale::from_accept_language("en;q=high"), None);
+        assert_eq!(Locale::from_accept_language(""), None);
+    }
+}
@@ -0,0 +1,80 @@
+use serde_json::{Map, Value};
+
+pub(super) const INVALID_FIELD: &str = "is invalid";
+
+/// Messages by error code
+pub(super) const ERRORS: &[(&str, &str)] = &[
+    ("validation_failed", "validation failed"),
+    ("invalid_bearer_token", "invalid or expired bearer token"),
+    ("missing_bearer_token", "missing bearer token"),
+    ("unsupported_media_type", "expected a json body"),
+    ("malformed_json", "malformed json body"),
+    ("invalid_body", "invalid request body"),
+    ("invalid_path", "invalid path parameter"),
+    ("invalid_query", "invalid query parameter"),
+    ("invalid_multipart", "invalid multipart body"),
+    ("missing_avatar", "missing avatar field"),
+    ("unsupported_image_type", "unsupported image type"),
+    ("image_too_large", "image too large"),
+    ("invalid_image", "invalid image"),
+    ("not_found", "not found"),
+    ("forbidden", "forbidden"),
+    (
+        "conflict",
+        "conflicts with the current state of the resource",
+    ),
+    ("service_unavailable", "service temporarily unavailable"),
+    ("internal_error", "internal error"),
+    ("invalid_credentials", "invalid credentials"),
+    ("reauthentication_required", "reauthentication required"),
+    ("reauthentication_failed", "reauthentication failed"),
+    ("invalid_token", "invalid or expired token"),
+    ("email_not_verified", "email not verified"),
+    (
+        "mfa_already_enabled",
+        "two-factor authentication already enabled",
+    ),
+    ("mfa_not_enrolled", "two-factor authentication not enrolled"),
+    ("invalid_mfa_code", "invalid two-factor code"),
+    ("invalid_passkey", "invalid passkey"),
+    ("too_many_attempts", "too many failed login attempts"),
+    ("account_locked", "account temporarily locked"),
+    ("account_disabled", "account disabled"),
+    ("account_suspended", "account suspended"),
+    ("account_banned", "account banned"),
+    ("account_deleted", "account scheduled for deletion"),
+    ("already_in_use", "already in use"),
+];
+
+pub(super) fn validation(
+    code: &str,
+    params: &Map<String, Value>,
+) -> Option<String> {
+    let message = match (code, params.get("min"), params.get("max")) {
+        ("length", Some(min), Some(max)) => {
+            format!("must be between {min} and {max} characters")
+        }
+        ("length", Some(min), None) => {
+            format!("must be at least {min} characters")
+        }
+        ("length", None, Some(max)) => {
+            format!("must be at most {max} characters")
+        }
+        ("range", Some(min), Some(max)) => {
+            format!("must be between {min} and {max}")
+        }
+        ("timezone", ..) => {
+            "must be an IANA timezone, e.g. Europe/Brussels".to_string()
+        }
+        ("suspended_until", ..) => {
+            "must be in the future and only goes with a suspension".to_string()
+        }
+        ("email", ..) => "must be a valid email".to_string(),
+        ("url", ..) => "must be a valid url".to_string(),
+        ("http_url", ..) => "must be a valid http or https url".to_string(),
+        ("required", ..) => "is required".to_string(),
+        _ => return None,
+    };
+
+    Some(message)
+}
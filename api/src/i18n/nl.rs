@@ -0,0 +1,83 @@
+use serde_json::{Map, Value};
+
+pub(super) const INVALID_FIELD: &str = "is ongeldig";
+
+/// Messages by error code
+pub(super) const ERRORS: &[(&str, &str)] = &[
+    ("validation_failed", "validatie mislukt"),
+    ("invalid_bearer_token", "ongeldig of verlopen toegangstoken"),
+    ("missing_bearer_token", "toegangstoken ontbreekt"),
+    ("unsupported_media_type", "json-body verwacht"),
+    ("malformed_json", "misvormde json-body"),
+    ("invalid_body", "ongeldige request-body"),
+    ("invalid_path", "ongeldige padparameter"),
+    ("invalid_query", "ongeldige queryparameter"),
+    ("invalid_multipart", "ongeldige multipart-body"),
+    ("missing_avatar", "avatarveld ontbreekt"),
+    ("unsupported_image_type", "niet-ondersteund afbeeldingstype"),
+    ("image_too_large", "afbeelding te groot"),
+    ("invalid_image", "ongeldige afbeelding"),
+    ("not_found", "niet gevonden"),
+    ("forbidden", "geen toegang"),
+    (
+        "conflict",
+        "in conflict met de huidige toestand van de resource",
+    ),
+    ("service_unavailable", "dienst tijdelijk niet beschikbaar"),
+    ("internal_error", "interne fout"),
+    ("invalid_credentials", "ongeldige inloggegevens"),
+    ("reauthentication_required", "opnieuw aanmelden vereist"),
+    ("reauthentication_failed", "opnieuw aanmelden mislukt"),
+    ("invalid_token", "ongeldige of verlopen token"),
+    ("email_not_verified", "e-mailadres niet geverifieerd"),
+    (
+        "mfa_already_enabled",
+        "tweestapsverificatie is al ingeschakeld",
+    ),
+    ("mfa_not_enrolled", "tweestapsverificatie is niet ingesteld"),
+    ("invalid_mfa_code", "ongeldige verificatiecode"),
+    ("invalid_passkey", "ongeldige passkey"),
+    ("too_many_attempts", "te veel mislukte aanmeldpogingen"),
+    ("account_locked", "account tijdelijk geblokkeerd"),
+    ("account_disabled", "account uitgeschakeld"),
+    ("account_suspended", "account geschorst"),
+    ("account_banned", "account verbannen"),
+    ("account_deleted", "account staat gepland voor verwijdering"),
+    ("already_in_use", "al in gebruik"),
+];
+
+pub(super) fn validation(
+    code: &str,
+    params: &Map<String, Value>,
+) -> Option<String> {
+    let message = match (code, params.get("min"), params.get("max")) {
+        ("length", Some(min), Some(max)) => {
+            format!("moet tussen {min} en {max} tekens lang zijn")
+        }
+        ("length", Some(min), None) => {
+            format!("moet minstens {min} tekens lang zijn")
+        }
+        ("length", None, Some(max)) => {
+            format!("mag maximaal {max} tekens lang zijn")
+        }
+        ("range", Some(min), Some(max)) => {
+            format!("moet tussen {min} en {max} liggen")
+        }
+        ("timezone", ..) => {
+            "moet een IANA-tijdzone zijn, bv. Europe/Brussels".to_string()
+        }
+        ("suspended_until", ..) => {
+            "moet in de toekomst liggen en hoort enkel bij een schorsing"
+                .to_string()
+        }
+        ("email", ..) => "moet een geldig e-mailadres zijn".to_string(),
+        ("url", ..) => "moet een geldige url zijn".to_string(),
+        ("http_url", ..) => {
+            "moet een geldige http- of https-url zijn".to_string()
+        }
+        ("required", ..) => "is verplicht".to_string(),
+        _ => return None,
+    };
+
+    Some(message)
+}
@@ -1,4 +1,6 @@
-use axum::{routing::IntoMakeService, Router};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{extract::connect_info::IntoMakeServiceWithConnectInfo, Router};
 use sea_orm::DatabaseConnection;
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
@@ -9,26 +11,91 @@ mod dto;
 mod error;
 mod extractor;
 mod handler;
+mod i18n;
+mod mail;
+mod middleware;
 mod service;
+mod storage;
 mod util;
 
+pub use mail::{LogMailer, Mail, MailError, Mailer};
+pub use storage::{LocalStorage, Storage, StorageError};
+
+use config::constant::ACCOUNT_PURGE_INTERVAL;
+use service::{export::ExportStore, passkey::PasskeyState};
+use util::throttle::LoginThrottle;
+
 type DbConn = DatabaseConnection;
 
 #[derive(Clone)]
 pub struct AppState {
     db: DbConn,
+    mailer: Arc<dyn Mailer>,
+    storage: Arc<dyn Storage>,
+    passkey: Arc<PasskeyState>,
+    login_throttle: Arc<LoginThrottle>,
+    exports: Arc<ExportStore>,
 }
 
-pub fn app(db_conn: DbConn) -> IntoMakeService<Router<()>> {
-    let state = AppState { db: db_conn };
+pub fn app(
+    db_conn: DbConn,
+    mailer: impl Mailer + 'static,
+    storage: impl Storage + 'static,
+) -> IntoMakeServiceWithConnectInfo<Router<()>, SocketAddr> {
+    // Don't let the first failed login pay for computing the dummy hash
+    lazy_static::initialize(&util::encryption::HASHING_POOL);
+    lazy_static::initialize(&util::encryption::DUMMY_PASSWORD_HASH);
+
+    let storage: Arc<dyn Storage> = Arc::new(storage);
+
+    tokio::spawn(purge_deleted_accounts(db_conn.clone(), storage.clone()));
 
-    let middleware_stack =
-        ServiceBuilder::new().layer(TraceLayer::new_for_http());
+    let state = AppState {
+        db: db_conn,
+        mailer: Arc::new(mailer),
+        storage,
+        passkey: Arc::new(PasskeyState::from_env()),
+        login_throttle: Arc::new(LoginThrottle::new()),
+        exports: Arc::new(ExportStore::new()),
+    };
+
+    let middleware_stack = ServiceBuilder::new()
+        .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(
+            middleware::request_id::request_id,
+        ))
+        .layer(axum::middleware::from_fn(middleware::locale::locale));
 
     Router::new()
         .nest("/user", handler::user::routes())
         .nest("/auth", handler::auth::routes())
+        .nest("/admin/users", handler::admin::routes())
+        .nest("/admin/roles", handler::admin::roles())
+        .merge(handler::avatar::files())
+        .merge(handler::metrics::routes())
         .layer(middleware_stack.into_inner())
         .with_state(state)
-        .into_make_service()
+        // The client address is needed to throttle logins per client
+        .into_make_service_with_connect_info::<SocketAddr>()
+}
+
+/// Purges the accounts past their deletion grace period, for as long as the
+/// server runs
+async fn purge_deleted_accounts(db: DbConn, storage: Arc<dyn Storage>) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(ACCOUNT_PURGE_INTERVAL));
+
+    loop {
+        interval.tick().await;
+
+        match service::user::UserService::purge_deleted(storage.as_ref(), &db)
+            .await
+        {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!("purged {purged} deleted accounts"),
+            Err(err) => {
+                tracing::error!("failed to purge deleted accounts: {:?}", err);
+            }
+        }
+    }
 }
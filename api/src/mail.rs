@@ -0,0 +1,32 @@
+use axum::async_trait;
+use thiserror::Error as ErrorTrait;
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, ErrorTrait)]
+#[error("failed to send mail: {0}")]
+pub struct MailError(pub String);
+
+/// Delivers mails sent by the api, implement this to plug in a mail provider
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), MailError>;
+}
+
+/// Writes mails to the log instead of delivering them, useful in development
+#[derive(Debug, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<(), MailError> {
+        tracing::info!("mail to {}: {}\n{}", mail.to, mail.subject, mail.body);
+
+        Ok(())
+    }
+}
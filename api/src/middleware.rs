@@ -0,0 +1,2 @@
+pub(crate) mod locale;
+pub(crate) mod request_id;
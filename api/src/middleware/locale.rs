@@ -0,0 +1,39 @@
+use axum::{
+    http::{header::ACCEPT_LANGUAGE, Request},
+    middleware::Next,
+    response::Response,
+};
+use headers::{authorization::Bearer, Authorization, HeaderMapExt};
+
+use crate::{dto::auth::SubAccesToken, i18n::Locale, util::jwt::ClaimsEncoded};
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// The language responses for the current request are written in
+pub(crate) fn current_locale() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// Negotiates the language from `Accept-Language`, falling back to the
+/// locale in the access token. That is the user's locale when the token was
+/// issued, a changed locale is only picked up after the next refresh
+pub(crate) async fn locale<B>(req: Request<B>, next: Next<B>) -> Response {
+    let locale = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|accept_language| accept_language.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .or_else(|| {
+            let Authorization(bearer) =
+                req.headers().typed_get::<Authorization<Bearer>>()?;
+            let claims: ClaimsEncoded<SubAccesToken> =
+                From::from(bearer.token().to_owned());
+
+            Some(claims.decode().ok()?.sub().locale)
+        })
+        .unwrap_or_default();
+
+    LOCALE.scope(locale, next.run(req)).await
+}
@@ -0,0 +1,44 @@
+use axum::{
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longer ids from the client are replaced instead of trusted
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The id of the request being handled, if any
+pub(crate) fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Reuses the `X-Request-Id` of the client or generates one, it is echoed in
+/// the response and available to the handlers through `current_request_id`
+pub(crate) async fn request_id<B>(req: Request<B>, next: Next<B>) -> Response {
+    let request_id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|request_id| request_id.to_str().ok())
+        .filter(|request_id| {
+            !request_id.is_empty() && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        })
+        .map_or_else(|| Uuid::new_v4().to_string(), ToOwned::to_owned);
+
+    let mut response =
+        REQUEST_ID.scope(request_id.clone(), next.run(req)).await;
+
+    if let Ok(request_id) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(X_REQUEST_ID.clone(), request_id);
+    }
+
+    response
+}
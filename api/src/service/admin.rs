@@ -0,0 +1,119 @@
+use uuid::Uuid;
+
+use crate::{
+    dto::{
+        admin::{
+            AdminStatusInput, AdminUpdateUserInput, AdminUserFilter,
+            AdminUserPayload, AdminUsersPayload,
+        },
+        auth::RefreshToken,
+        role::Role,
+        user::{AccountStatus, User},
+    },
+    error::{ErrorRepr, ResultRepr},
+    mail::Mailer,
+    service::user::UserService,
+    DbConn,
+};
+
+pub(crate) struct AdminService;
+
+impl AdminService {
+    pub(crate) async fn list(
+        filter: AdminUserFilter,
+        db: &DbConn,
+    ) -> ResultRepr<AdminUsersPayload> {
+        let (users, total) = User::list(&filter, db).await?;
+        let mut roles = Role::names_by_user_uuids(
+            users.iter().map(|user| user.uuid).collect(),
+            db,
+        )
+        .await?;
+
+        Ok(AdminUsersPayload {
+            users: users
+                .into_iter()
+                .map(|user| {
+                    let roles = roles.remove(&user.uuid).unwrap_or_default();
+                    AdminUserPayload::new(user, roles)
+                })
+                .collect(),
+            page: filter.page,
+            per_page: filter.per_page,
+            total,
+        })
+    }
+
+    pub(crate) async fn get(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<AdminUserPayload> {
+        let user = UserService::get_by_uuid(uuid, db).await?;
+        let roles = Role::get_by_user_uuid(uuid, db)
+            .await?
+            .into_iter()
+            .map(|role| role.name)
+            .collect();
+
+        Ok(AdminUserPayload::new(user, roles))
+    }
+
+    pub(crate) async fn update(
+        uuid: Uuid,
+        input: AdminUpdateUserInput,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<AdminUserPayload> {
+        UserService::apply_update(uuid, input.into(), None, mailer, db).await?;
+
+        Self::get(uuid, db).await
+    }
+
+    /// Any status but active also ends every session of the user
+    pub(crate) async fn set_status(
+        admin_uuid: Uuid,
+        uuid: Uuid,
+        input: AdminStatusInput,
+        db: &DbConn,
+    ) -> ResultRepr<AdminUserPayload> {
+        let active = input.status == AccountStatus::Active;
+
+        // Admins could lock themselves out
+        if !active && admin_uuid == uuid {
+            return Err(ErrorRepr::Forbidden);
+        }
+
+        User::update_status(
+            uuid,
+            input.status,
+            input.reason,
+            input.suspended_until,
+            db,
+        )
+        .await?;
+        if !active {
+            RefreshToken::drop_by_user_uuid(uuid, db).await?;
+        }
+
+        Self::get(uuid, db).await
+    }
+
+    pub(crate) async fn logout(uuid: Uuid, db: &DbConn) -> ResultRepr<()> {
+        // Fails when the user doesn't exist, instead of silently doing nothing
+        UserService::get_by_uuid(uuid, db).await?;
+        RefreshToken::drop_by_user_uuid(uuid, db).await?;
+
+        Ok(())
+    }
+
+    /// Mails the user a password reset link
+    pub(crate) async fn reset_password(
+        uuid: Uuid,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let user = UserService::get_by_uuid(uuid, db).await?;
+
+        UserService::forgot_password(user.email, mailer, db).await
+    }
+}
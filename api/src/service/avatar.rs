@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    config::{constant::AVATAR_SIZES, env::API_URL},
+    dto::user::{AvatarPayload, User},
+    error::ResultRepr,
+    storage::Storage,
+    util::avatar::spawn_thumbnails,
+    DbConn,
+};
+
+pub(crate) struct AvatarService;
+
+impl AvatarService {
+    /// Stores the thumbnails of the upload and makes it the avatar of the
+    /// user, the previous one is released
+    pub(crate) async fn upload(
+        user_uuid: Uuid,
+        bytes: Vec<u8>,
+        storage: &dyn Storage,
+        db: &DbConn,
+    ) -> ResultRepr<AvatarPayload> {
+        // Content-addressed, the same upload always ends up at the same url
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+
+        // Decoding and resizing would hold up the other requests
+        let thumbnails = spawn_thumbnails(bytes).await?;
+
+        let mut sizes = BTreeMap::new();
+        for (size, png) in thumbnails {
+            storage.put(&Self::key(&hash, size), png).await?;
+            sizes.insert(size, Self::url(&hash, size));
+        }
+
+        let previous = User::get_by_uuid(user_uuid, db).await?.avatar_url;
+
+        let avatar_url = Self::url(&hash, AVATAR_SIZES[0]);
+        User::update_avatar_url(user_uuid, Some(avatar_url.clone()), db)
+            .await?;
+
+        if let Some(previous) = previous.filter(|url| *url != avatar_url) {
+            Self::release(&previous, storage, db).await?;
+        }
+
+        Ok(AvatarPayload { avatar_url, sizes })
+    }
+
+    /// Files are content-addressed and shared by users that uploaded the same
+    /// image, so they're only deleted once no user has the avatar anymore.
+    /// An upload of the same image racing this can lose its files, uploading
+    /// it again brings them back
+    pub(crate) async fn release(
+        avatar_url: &str,
+        storage: &dyn Storage,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let Some(hash) = Self::hash_of(avatar_url) else {
+            return Ok(());
+        };
+
+        if User::avatar_in_use(avatar_url, db).await? {
+            return Ok(());
+        }
+
+        for size in AVATAR_SIZES {
+            storage.delete(&Self::key(hash, size)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// `None` for anything that isn't a stored thumbnail
+    pub(crate) async fn get(
+        hash: &str,
+        file: &str,
+        storage: &dyn Storage,
+    ) -> ResultRepr<Option<Vec<u8>>> {
+        let is_hash = Self::is_hash(hash);
+        let size = file
+            .strip_suffix(".png")
+            .and_then(|size| size.parse::<u32>().ok())
+            .filter(|size| AVATAR_SIZES.contains(size));
+
+        let Some(size) = size.filter(|_| is_hash) else {
+            return Ok(None);
+        };
+
+        let png = storage.get(&Self::key(hash, size)).await?;
+
+        Ok(png)
+    }
+
+    fn is_hash(hash: &str) -> bool {
+        hash.len() == 64
+            && hash
+                .bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    }
+
+    /// `None` for urls this api didn't hand out
+    fn hash_of(avatar_url: &str) -> Option<&str> {
+        let (hash, _file) = avatar_url
+            .strip_prefix(API_URL.as_str())?
+            .strip_prefix("/avatars/")?
+            .split_once('/')?;
+
+        Self::is_hash(hash).then_some(hash)
+    }
+
+    fn key(hash: &str, size: u32) -> String {
+        format!("avatars/{hash}/{size}.png")
+    }
+
+    fn url(hash: &str, size: u32) -> String {
+        format!("{}/{}", *API_URL, Self::key(hash, size))
+    }
+}
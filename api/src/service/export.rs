@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    config::constant::USER_EXPORT_TIMEOUT,
+    dto::export::{UserExport, UserExportProfile},
+    error::ResultRepr,
+    service::{
+        mfa::MfaService, passkey::PasskeyService, role::RoleService,
+        session::SessionService, user::UserService,
+    },
+    util::now_utc,
+    DbConn,
+};
+
+enum Export {
+    Pending,
+    Ready(Instant, Box<UserExport>),
+}
+
+#[derive(Debug)]
+pub(crate) enum ExportStatus {
+    Ready(Box<UserExport>),
+    Pending,
+    /// Never requested, already downloaded or expired
+    Missing,
+}
+
+/// Exports that are being generated or waiting to be downloaded, at most one
+/// per user
+pub(crate) struct ExportStore {
+    timeout: Duration,
+    exports: Mutex<HashMap<Uuid, Export>>,
+}
+
+impl ExportStore {
+    pub(crate) fn new() -> Self {
+        Self::with_timeout(Duration::from_secs(USER_EXPORT_TIMEOUT))
+    }
+
+    fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            exports: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Marks an export as pending, false when one already is
+    pub(crate) fn start(&self, user_uuid: Uuid) -> bool {
+        let mut exports = self.exports.lock().expect("export store poisoned");
+
+        // Forget exports that were never downloaded
+        exports.retain(|_, export| match export {
+            Export::Pending => true,
+            Export::Ready(finished, _) => finished.elapsed() < self.timeout,
+        });
+
+        if matches!(exports.get(&user_uuid), Some(Export::Pending)) {
+            return false;
+        }
+        exports.insert(user_uuid, Export::Pending);
+
+        true
+    }
+
+    /// Failed exports are dropped, the next request starts over
+    pub(crate) fn finish(
+        &self,
+        user_uuid: Uuid,
+        export: ResultRepr<UserExport>,
+    ) {
+        let mut exports = self.exports.lock().expect("export store poisoned");
+
+        match export {
+            Ok(export) => {
+                let export = Export::Ready(Instant::now(), Box::new(export));
+                exports.insert(user_uuid, export);
+            }
+            Err(err) => {
+                tracing::error!("failed to export user data: {:?}", err);
+                exports.remove(&user_uuid);
+            }
+        }
+    }
+
+    /// A ready export can be taken only once
+    pub(crate) fn take(&self, user_uuid: Uuid) -> ExportStatus {
+        let mut exports = self.exports.lock().expect("export store poisoned");
+
+        match exports.remove(&user_uuid) {
+            Some(Export::Ready(finished, export))
+                if finished.elapsed() < self.timeout =>
+            {
+                ExportStatus::Ready(export)
+            }
+            Some(Export::Ready(..)) | None => ExportStatus::Missing,
+            Some(Export::Pending) => {
+                exports.insert(user_uuid, Export::Pending);
+                ExportStatus::Pending
+            }
+        }
+    }
+}
+
+pub(crate) struct ExportService;
+
+impl ExportService {
+    pub(crate) async fn generate(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<UserExport> {
+        let user = UserService::get_by_uuid(user_uuid, db).await?;
+        let grants = RoleService::grants(user_uuid, db).await?;
+        let sessions = SessionService::list(user_uuid, db).await?;
+
+        Ok(UserExport {
+            generated_at: now_utc(),
+            user: UserExportProfile::new(user, grants.roles),
+            sessions: sessions.sessions,
+            trusted_devices: sessions.trusted_devices,
+            passkeys: PasskeyService::list(user_uuid, db).await?,
+            mfa: MfaService::settings(user_uuid, db).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dto::{mfa::MfaSettingsPayload, user::User},
+        error::UserError,
+    };
+
+    fn export() -> ResultRepr<UserExport> {
+        Ok(UserExport {
+            generated_at: now_utc(),
+            user: UserExportProfile::new(User::default(), Vec::new()),
+            sessions: Vec::new(),
+            trusted_devices: Vec::new(),
+            passkeys: Vec::new(),
+            mfa: MfaSettingsPayload {
+                totp: false,
+                email: false,
+                passkey: false,
+                passkeys: 0,
+                recovery_codes: 0,
+            },
+        })
+    }
+
+    #[test]
+    fn ready_export_is_taken_once() {
+        let store = ExportStore::new();
+        let user_uuid = Uuid::new_v4();
+
+        assert!(store.start(user_uuid));
+        assert!(!store.start(user_uuid));
+        assert!(matches!(store.take(user_uuid), ExportStatus::Pending));
+
+        store.finish(user_uuid, export());
+
+        assert!(matches!(store.take(user_uuid), ExportStatus::Ready(_)));
+        assert!(matches!(store.take(user_uuid), ExportStatus::Missing));
+    }
+
+    #[test]
+    fn expired_export_is_missing() {
+        let store = ExportStore::with_timeout(Duration::ZERO);
+        let user_uuid = Uuid::new_v4();
+
+        assert!(store.start(user_uuid));
+        store.finish(user_uuid, export());
+
+        assert!(matches!(store.take(user_uuid), ExportStatus::Missing));
+    }
+
+    #[test]
+    fn failed_export_can_be_restarted() {
+        let store = ExportStore::new();
+        let user_uuid = Uuid::new_v4();
+
+        assert!(store.start(user_uuid));
+        store.finish(user_uuid, Err(UserError::NotFound.into()));
+
+        assert!(matches!(store.take(user_uuid), ExportStatus::Missing));
+        assert!(store.start(user_uuid));
+    }
+}
@@ -0,0 +1,416 @@
+use std::{net::IpAddr, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{
+    config::constant::{
+        MFA_EMAIL_CODE_ATTEMPTS, MFA_EMAIL_CODE_RESEND_COOLDOWN,
+        MFA_EMAIL_CODE_TIMEOUT, MFA_LOGIN_ATTEMPTS, MFA_TOKEN_TIMEOUT,
+        RECOVERY_CODE_COUNT,
+    },
+    dto::{
+        auth::{OneTimeToken, SubAccesToken, TokenPurpose},
+        mfa::{
+            MfaLoginInput, MfaMethod, MfaSettingsPayload, RecoveryCode,
+            SubMfaToken, TotpEnrollmentPayload, UpdateMfaSettingsInput,
+        },
+        passkey::UserPasskey,
+        user::User,
+    },
+    error::{ResultRepr, UserError},
+    mail::{Mail, Mailer},
+    service::user::UserService,
+    util::{
+        jwt::{Claims, ClaimsEncoded, Decoded},
+        now_utc,
+        throttle::LoginThrottle,
+        token::{generate_numeric_code, generate_recovery_code, hash_token},
+        totp,
+    },
+    DbConn,
+};
+
+pub(crate) struct MfaService;
+
+impl MfaService {
+    /// The second factors the user can use, empty if 2FA is not enabled
+    pub(crate) async fn methods(
+        user: &User,
+        db: &DbConn,
+    ) -> ResultRepr<Vec<MfaMethod>> {
+        let mut methods = Vec::new();
+
+        if user.totp_enabled_at.is_some() {
+            methods.push(MfaMethod::Totp);
+            methods.push(MfaMethod::RecoveryCode);
+        }
+
+        if user.mfa_passkey_enabled
+            && UserPasskey::count_by_user_uuid(user.uuid, db).await? > 0
+        {
+            methods.push(MfaMethod::Passkey);
+        }
+
+        if user.mfa_email_enabled {
+            methods.push(MfaMethod::Email);
+        }
+
+        Ok(methods)
+    }
+
+    pub(crate) async fn settings(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<MfaSettingsPayload> {
+        let user = User::get_by_uuid(uuid, db).await?;
+
+        Ok(MfaSettingsPayload {
+            totp: user.totp_enabled_at.is_some(),
+            email: user.mfa_email_enabled,
+            passkey: user.mfa_passkey_enabled,
+            passkeys: UserPasskey::count_by_user_uuid(uuid, db).await?,
+            recovery_codes: RecoveryCode::count_by_user_uuid(uuid, db).await?,
+        })
+    }
+
+    pub(crate) async fn update_settings(
+        claims: &Decoded<SubAccesToken>,
+        input: UpdateMfaSettingsInput,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<MfaSettingsPayload> {
+        UserService::reauthenticate(
+            claims,
+            input.current_password,
+            throttle,
+            db,
+        )
+        .await?;
+
+        let uuid = claims.sub.user_uuid;
+
+        let user = User::get_by_uuid(uuid, db).await?;
+
+        if let Some(email) = input.email {
+            // Codes can only be delivered to an address that is known to work
+            if email && user.email_verified_at.is_none() {
+                return Err(UserError::EmailNotVerified.into());
+            }
+
+            User::update_mfa_email_enabled(uuid, email, db).await?;
+            if !email {
+                OneTimeToken::drop_by_user_uuid(
+                    uuid,
+                    TokenPurpose::MfaEmail,
+                    db,
+                )
+                .await?;
+            }
+        }
+
+        if let Some(passkey) = input.passkey {
+            if passkey && UserPasskey::count_by_user_uuid(uuid, db).await? == 0
+            {
+                return Err(UserError::NoPasskey.into());
+            }
+
+            User::update_mfa_passkey_enabled(uuid, passkey, db).await?;
+        }
+
+        if input.email.is_some() || input.passkey.is_some() {
+            User::rotate_security_stamp(uuid, db).await?;
+        }
+
+        Self::settings(uuid, db).await
+    }
+
+    /// Mails a new login code, replacing any earlier one
+    pub(crate) async fn send_email_code(
+        user: &User,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        OneTimeToken::drop_by_user_uuid(user.uuid, TokenPurpose::MfaEmail, db)
+            .await?;
+
+        let code = generate_numeric_code();
+        let expiry_date =
+            now_utc() + Duration::from_secs(MFA_EMAIL_CODE_TIMEOUT);
+
+        OneTimeToken::new(
+            user.uuid,
+            TokenPurpose::MfaEmail,
+            Self::email_code_hash(user.uuid, &code),
+            expiry_date,
+            db,
+        )
+        .await?;
+
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: "Your login code".to_string(),
+            body: format!(
+                "Your login code is {}, it is valid for {} minutes",
+                code,
+                MFA_EMAIL_CODE_TIMEOUT / 60
+            ),
+        };
+        mailer.send(mail).await?;
+
+        Ok(())
+    }
+
+    /// Sends another code for a login that is waiting on its second factor,
+    /// at most once per `MFA_EMAIL_CODE_RESEND_COOLDOWN`
+    pub(crate) async fn resend_email_code(
+        mfa_token: ClaimsEncoded<SubMfaToken>,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let uuid = mfa_token
+            .decode()
+            .map_err(|_| UserError::InvalidToken)?
+            .sub()
+            .user_uuid;
+        let user = User::get_by_uuid(uuid, db).await?;
+
+        if !user.mfa_email_enabled {
+            return Err(UserError::MfaNotEnrolled.into());
+        }
+
+        let cooldown = Duration::from_secs(MFA_EMAIL_CODE_RESEND_COOLDOWN);
+        if OneTimeToken::issued_since(
+            user.uuid,
+            TokenPurpose::MfaEmail,
+            now_utc() - cooldown,
+            db,
+        )
+        .await?
+        {
+            return Err(UserError::LoginThrottled(cooldown).into());
+        }
+
+        Self::send_email_code(&user, mailer, db).await
+    }
+
+    /// Proves the first factor was valid, it can be exchanged for a session
+    /// once
+    pub(crate) async fn issue_token(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<ClaimsEncoded<SubMfaToken>> {
+        // Abandoned logins leave their token behind
+        OneTimeToken::drop_expired_by_user_uuid(
+            uuid,
+            TokenPurpose::MfaLogin,
+            db,
+        )
+        .await?;
+
+        let sub = SubMfaToken::new(uuid);
+        let expiry_date = now_utc() + Duration::from_secs(MFA_TOKEN_TIMEOUT);
+
+        OneTimeToken::new(
+            uuid,
+            TokenPurpose::MfaLogin,
+            Self::token_hash(&sub),
+            expiry_date,
+            db,
+        )
+        .await?;
+
+        Claims::new(sub)
+    }
+
+    /// Ends the token once the second factor has been verified
+    pub(crate) async fn consume_token(
+        sub: &SubMfaToken,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        OneTimeToken::consume(
+            Self::token_hash(sub),
+            TokenPurpose::MfaLogin,
+            db,
+        )
+        .await?
+        .ok_or(UserError::InvalidToken)?;
+
+        Ok(())
+    }
+
+    fn token_hash(sub: &SubMfaToken) -> String {
+        hash_token(&sub.jti.to_string())
+    }
+
+    /// The unique token hash column is shared, so salt the short code
+    fn email_code_hash(uuid: Uuid, code: &str) -> String {
+        hash_token(&format!("{uuid}:{code}"))
+    }
+
+    /// Stores a new pending secret, it is only used after being confirmed
+    pub(crate) async fn enroll_totp(
+        claims: &Decoded<SubAccesToken>,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<TotpEnrollmentPayload> {
+        UserService::reauthenticate(claims, current_password, throttle, db)
+            .await?;
+
+        let uuid = claims.sub.user_uuid;
+
+        let user = User::get_by_uuid(uuid, db).await?;
+        if user.totp_enabled_at.is_some() {
+            return Err(UserError::MfaAlreadyEnabled.into());
+        }
+
+        let secret = totp::generate_secret();
+        let otpauth_uri = totp::otpauth_uri(&secret, user.email)?;
+
+        User::update_totp(uuid, Some(secret.clone()), None, db).await?;
+        User::rotate_security_stamp(uuid, db).await?;
+
+        Ok(TotpEnrollmentPayload {
+            secret,
+            otpauth_uri,
+        })
+    }
+
+    /// Enables TOTP and returns a fresh set of recovery codes
+    pub(crate) async fn confirm_totp(
+        uuid: Uuid,
+        code: String,
+        db: &DbConn,
+    ) -> ResultRepr<Vec<String>> {
+        let user = User::get_by_uuid(uuid, db).await?;
+        if user.totp_enabled_at.is_some() {
+            return Err(UserError::MfaAlreadyEnabled.into());
+        }
+
+        let secret = user.totp_secret.ok_or(UserError::MfaNotEnrolled)?;
+        let step = totp::verify_code(&secret, &code, user.totp_last_step)?
+            .ok_or(UserError::InvalidMfaCode)?;
+
+        User::update_totp(uuid, Some(secret), Some(now_utc()), db).await?;
+        // The confirming code can't be used to log in as well
+        User::update_totp_last_step(uuid, step, db).await?;
+
+        Self::regenerate_recovery_codes(uuid, db).await
+    }
+
+    pub(crate) async fn disable_totp(
+        claims: &Decoded<SubAccesToken>,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        UserService::reauthenticate(claims, current_password, throttle, db)
+            .await?;
+
+        let uuid = claims.sub.user_uuid;
+
+        User::update_totp(uuid, None, None, db).await?;
+        RecoveryCode::drop_by_user_uuid(uuid, db).await?;
+        User::rotate_security_stamp(uuid, db).await?;
+
+        Ok(())
+    }
+
+    /// Only the hashes are stored, the codes can't be shown again
+    async fn regenerate_recovery_codes(
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<Vec<String>> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        let code_hashes = codes.iter().map(|code| hash_token(code)).collect();
+
+        RecoveryCode::replace(uuid, code_hashes, db).await?;
+        User::rotate_security_stamp(uuid, db).await?;
+
+        Ok(codes)
+    }
+
+    /// Verifies the second factor and finishes the login
+    pub(crate) async fn login(
+        input: MfaLoginInput,
+        ip: IpAddr,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<User> {
+        let sub = input
+            .mfa_token
+            .decode()
+            .map_err(|_| UserError::InvalidToken)?
+            .sub();
+        let uuid = sub.user_uuid;
+        let user = User::get_by_uuid(uuid, db).await?;
+        // The account may have changed since the first factor
+        UserService::ensure_can_login(&user)?;
+
+        // Wrong codes count as failed logins, so a new `mfa_token` doesn't
+        // buy more guesses
+        throttle.check(&user.email, ip)?;
+        let attempt = OneTimeToken::record_attempt(
+            Self::token_hash(&sub),
+            TokenPurpose::MfaLogin,
+            MFA_LOGIN_ATTEMPTS,
+            db,
+        )
+        .await?;
+        if !attempt {
+            return Err(UserError::InvalidToken.into());
+        }
+
+        if !Self::methods(&user, db).await?.contains(&input.method) {
+            return Err(UserError::InvalidMfaCode.into());
+        }
+
+        let valid = match input.method {
+            MfaMethod::Totp => match user.totp_secret {
+                Some(ref secret) => {
+                    match totp::verify_code(
+                        secret,
+                        &input.code,
+                        user.totp_last_step,
+                    )? {
+                        // Loses from a concurrent login with the same code
+                        Some(step) => {
+                            User::update_totp_last_step(uuid, step, db).await?
+                        }
+                        None => false,
+                    }
+                }
+                None => false,
+            },
+            MfaMethod::RecoveryCode => {
+                let code_hash = hash_token(input.code.trim());
+                RecoveryCode::consume(uuid, code_hash, db).await?
+            }
+            // Has its own ceremony, see `PasskeyService::finish_mfa`
+            MfaMethod::Passkey => false,
+            MfaMethod::Email => {
+                OneTimeToken::verify_code(
+                    uuid,
+                    TokenPurpose::MfaEmail,
+                    Self::email_code_hash(uuid, input.code.trim()),
+                    MFA_EMAIL_CODE_ATTEMPTS,
+                    db,
+                )
+                .await?
+            }
+        };
+
+        if !valid {
+            throttle.record_failure(&user.email, ip);
+            return Err(UserError::InvalidMfaCode.into());
+        }
+
+        Self::consume_token(&sub, db).await?;
+        throttle.record_success(&user.email);
+        User::update_last_login(user.id, db).await?;
+
+        Ok(user)
+    }
+}
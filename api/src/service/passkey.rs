@@ -0,0 +1,424 @@
+use std::{net::IpAddr, time::Duration};
+
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    Base64UrlSafeData, Passkey, PasskeyAuthentication, PasskeyRegistration,
+    PublicKeyCredential, RequestChallengeResponse, Url, Webauthn,
+    WebauthnBuilder, WebauthnError,
+};
+use webauthn_rs_proto::{
+    AllowCredentials, PublicKeyCredentialRequestOptions, UserVerificationPolicy,
+};
+
+use crate::{
+    config::{
+        constant::{
+            PASSKEY_CEREMONY_CAPACITY, PASSKEY_CEREMONY_TIMEOUT,
+            WEBAUTHN_RP_NAME,
+        },
+        env::{WEBAUTHN_RP_ID, WEBAUTHN_RP_ORIGIN},
+    },
+    dto::{
+        auth::SubAccesToken,
+        mfa::SubMfaToken,
+        passkey::{
+            FinishPasskeyRegistrationInput, PasskeyAuthenticationPayload,
+            PasskeyPayload, PasskeyRegistrationPayload, UserPasskey,
+        },
+        user::User,
+    },
+    error::{ErrorRepr, ResultRepr, UserError},
+    service::{mfa::MfaService, user::UserService},
+    util::{
+        ceremony::CeremonyStore,
+        jwt::{ClaimsEncoded, Decoded},
+        throttle::LoginThrottle,
+    },
+    DbConn,
+};
+
+/// Same as the challenges webauthn-rs generates
+const DECOY_CHALLENGE_SIZE: usize = 32;
+const DECOY_AUTHENTICATOR_TIMEOUT: u32 = 60_000;
+
+/// An ongoing authentication ceremony
+enum Authentication {
+    Passkey(Uuid, PasskeyAuthentication),
+    /// Stands in for an unknown email or a user without passkeys, so
+    /// `start_login` doesn't reveal which accounts exist
+    Decoy,
+}
+
+/// WebAuthn configuration and the state of the ongoing ceremonies
+pub(crate) struct PasskeyState {
+    webauthn: Webauthn,
+    registrations: CeremonyStore<(Uuid, PasskeyRegistration)>,
+    authentications: CeremonyStore<Authentication>,
+    /// Derives the credential ids of decoys, random so they can't be told
+    /// apart from real ones
+    decoy_key: [u8; 32],
+}
+
+impl PasskeyState {
+    pub(crate) fn from_env() -> Self {
+        let rp_origin = Url::parse(&WEBAUTHN_RP_ORIGIN)
+            .expect("WEBAUTHN_RP_ORIGIN must be a valid url");
+        let webauthn = WebauthnBuilder::new(&WEBAUTHN_RP_ID, &rp_origin)
+            .and_then(|builder| builder.rp_name(WEBAUTHN_RP_NAME).build())
+            .expect("invalid WebAuthn configuration");
+        let timeout = Duration::from_secs(PASSKEY_CEREMONY_TIMEOUT);
+        let mut decoy_key = [0; 32];
+        OsRng.fill_bytes(&mut decoy_key);
+
+        Self {
+            webauthn,
+            registrations: CeremonyStore::new(
+                timeout,
+                PASSKEY_CEREMONY_CAPACITY,
+            ),
+            authentications: CeremonyStore::new(
+                timeout,
+                PASSKEY_CEREMONY_CAPACITY,
+            ),
+            decoy_key,
+        }
+    }
+}
+
+/// The same email always gets the same credential id, like a real user
+/// with a single passkey would
+fn decoy_credential_id(key: &[u8], email: &str) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(key)
+        .chain_update(email.trim().to_lowercase())
+        .finalize()
+        .to_vec()
+}
+
+pub(crate) struct PasskeyService;
+
+impl PasskeyService {
+    pub(crate) async fn list(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<Vec<PasskeyPayload>> {
+        let passkeys = UserPasskey::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(passkeys)
+    }
+
+    /// Passkeys can be a second factor, so removing one requires
+    /// reauthentication
+    pub(crate) async fn delete(
+        claims: &Decoded<SubAccesToken>,
+        id: i32,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        UserService::reauthenticate(claims, current_password, throttle, db)
+            .await?;
+
+        let user_uuid = claims.sub.user_uuid;
+        UserPasskey::drop_by_id(id, user_uuid, db).await?;
+        User::rotate_security_stamp(user_uuid, db).await?;
+
+        Ok(())
+    }
+
+    /// A passkey is enough to log in, so adding one requires reauthentication
+    pub(crate) async fn start_registration(
+        state: &PasskeyState,
+        claims: &Decoded<SubAccesToken>,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<PasskeyRegistrationPayload> {
+        UserService::reauthenticate(claims, current_password, throttle, db)
+            .await?;
+
+        let user_uuid = claims.sub.user_uuid;
+        let user = User::get_by_uuid(user_uuid, db).await?;
+
+        // Prevents registering the same authenticator twice
+        let exclude_credentials = UserPasskey::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .map(|user_passkey| user_passkey.passkey.cred_id().clone())
+            .collect();
+
+        let (options, registration) =
+            state.webauthn.start_passkey_registration(
+                user.uuid,
+                &user.email,
+                &user.display_name,
+                Some(exclude_credentials),
+            )?;
+
+        let ceremony_id = state
+            .registrations
+            .insert((user.uuid, registration))
+            .ok_or(ErrorRepr::CeremoniesFull)?;
+
+        Ok(PasskeyRegistrationPayload {
+            ceremony_id,
+            options,
+        })
+    }
+
+    pub(crate) async fn finish_registration(
+        state: &PasskeyState,
+        user_uuid: Uuid,
+        input: FinishPasskeyRegistrationInput,
+        db: &DbConn,
+    ) -> ResultRepr<PasskeyPayload> {
+        let (ceremony_user_uuid, registration) = state
+            .registrations
+            .take(input.ceremony_id)
+            .ok_or(UserError::InvalidToken)?;
+
+        if ceremony_user_uuid != user_uuid {
+            return Err(UserError::InvalidToken.into());
+        }
+
+        let passkey = state
+            .webauthn
+            .finish_passkey_registration(&input.credential, &registration)?;
+
+        let user_passkey =
+            UserPasskey::create(user_uuid, input.name, passkey, db).await?;
+
+        Ok(user_passkey.into())
+    }
+
+    async fn passkeys(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<Vec<Passkey>> {
+        let passkeys = UserPasskey::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .map(|user_passkey| user_passkey.passkey)
+            .collect();
+
+        Ok(passkeys)
+    }
+
+    fn start_authentication(
+        state: &PasskeyState,
+        user_uuid: Uuid,
+        passkeys: &[Passkey],
+    ) -> ResultRepr<PasskeyAuthenticationPayload> {
+        if passkeys.is_empty() {
+            return Err(UserError::NoPasskey.into());
+        }
+
+        let (options, authentication) =
+            state.webauthn.start_passkey_authentication(passkeys)?;
+
+        Self::insert_authentication(
+            state,
+            Authentication::Passkey(user_uuid, authentication),
+            options,
+        )
+    }
+
+    /// Looks like a challenge for a single passkey, but can't be completed
+    fn start_decoy(
+        state: &PasskeyState,
+        email: &str,
+    ) -> ResultRepr<PasskeyAuthenticationPayload> {
+        let mut challenge = vec![0; DECOY_CHALLENGE_SIZE];
+        OsRng.fill_bytes(&mut challenge);
+
+        let options = RequestChallengeResponse {
+            public_key: PublicKeyCredentialRequestOptions {
+                challenge: Base64UrlSafeData(challenge),
+                timeout: Some(DECOY_AUTHENTICATOR_TIMEOUT),
+                rp_id: WEBAUTHN_RP_ID.clone(),
+                allow_credentials: vec![AllowCredentials {
+                    type_: "public-key".to_string(),
+                    id: Base64UrlSafeData(decoy_credential_id(
+                        &state.decoy_key,
+                        email,
+                    )),
+                    transports: None,
+                }],
+                user_verification: UserVerificationPolicy::Preferred,
+                extensions: None,
+            },
+            mediation: None,
+        };
+
+        Self::insert_authentication(state, Authentication::Decoy, options)
+    }
+
+    fn insert_authentication(
+        state: &PasskeyState,
+        authentication: Authentication,
+        options: RequestChallengeResponse,
+    ) -> ResultRepr<PasskeyAuthenticationPayload> {
+        let ceremony_id = state
+            .authentications
+            .insert(authentication)
+            .ok_or(ErrorRepr::CeremoniesFull)?;
+
+        Ok(PasskeyAuthenticationPayload {
+            ceremony_id,
+            options,
+        })
+    }
+
+    /// Verifies the assertion and returns the uuid of the authenticated user
+    async fn finish_authentication(
+        state: &PasskeyState,
+        ceremony_id: Uuid,
+        credential: PublicKeyCredential,
+        db: &DbConn,
+    ) -> ResultRepr<Uuid> {
+        let authentication = state
+            .authentications
+            .take(ceremony_id)
+            .ok_or(UserError::InvalidToken)?;
+
+        // Fails like a credential that doesn't verify
+        let Authentication::Passkey(user_uuid, authentication) = authentication
+        else {
+            return Err(WebauthnError::AuthenticationFailure.into());
+        };
+
+        let result = state
+            .webauthn
+            .finish_passkey_authentication(&credential, &authentication)?;
+
+        let mut user_passkey = UserPasskey::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .find(|user_passkey| {
+                user_passkey.passkey.cred_id() == result.cred_id()
+            })
+            .ok_or(UserError::NoPasskey)?;
+
+        user_passkey.passkey.update_credential(&result);
+        user_passkey.update_used(result.counter(), db).await?;
+
+        Ok(user_uuid)
+    }
+
+    /// Passkeys are phishing resistant and verify the user, so they can be
+    /// used to log in without a password. Unknown emails and users without
+    /// passkeys get a decoy, so this doesn't reveal which accounts exist
+    pub(crate) async fn start_login(
+        state: &PasskeyState,
+        email: String,
+        ip: IpAddr,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<PasskeyAuthenticationPayload> {
+        throttle.check(&email, ip)?;
+
+        let user = User::get_by_email(email.clone(), db).await?;
+
+        // Looked up either way, so the timing doesn't give unknown emails away
+        let user_uuid = user.map_or(Uuid::nil(), |user| user.uuid);
+        let passkeys = Self::passkeys(user_uuid, db).await?;
+        if passkeys.is_empty() {
+            return Self::start_decoy(state, &email);
+        }
+
+        Self::start_authentication(state, user_uuid, &passkeys)
+    }
+
+    pub(crate) async fn finish_login(
+        state: &PasskeyState,
+        ceremony_id: Uuid,
+        credential: PublicKeyCredential,
+        db: &DbConn,
+    ) -> ResultRepr<User> {
+        let user_uuid =
+            Self::finish_authentication(state, ceremony_id, credential, db)
+                .await?;
+
+        let user = User::get_by_uuid(user_uuid, db).await?;
+        UserService::ensure_can_login(&user)?;
+        User::update_last_login(user.id, db).await?;
+
+        Ok(user)
+    }
+
+    pub(crate) async fn start_mfa(
+        state: &PasskeyState,
+        mfa_token: ClaimsEncoded<SubMfaToken>,
+        db: &DbConn,
+    ) -> ResultRepr<PasskeyAuthenticationPayload> {
+        let user_uuid = mfa_token
+            .decode()
+            .map_err(|_| UserError::InvalidToken)?
+            .sub()
+            .user_uuid;
+
+        let user = User::get_by_uuid(user_uuid, db).await?;
+        if !user.mfa_passkey_enabled {
+            return Err(UserError::MfaNotEnrolled.into());
+        }
+
+        let passkeys = Self::passkeys(user_uuid, db).await?;
+
+        Self::start_authentication(state, user_uuid, &passkeys)
+    }
+
+    pub(crate) async fn finish_mfa(
+        state: &PasskeyState,
+        mfa_token: ClaimsEncoded<SubMfaToken>,
+        ceremony_id: Uuid,
+        credential: PublicKeyCredential,
+        db: &DbConn,
+    ) -> ResultRepr<User> {
+        let sub = mfa_token
+            .decode()
+            .map_err(|_| UserError::InvalidToken)?
+            .sub();
+        let user_uuid = sub.user_uuid;
+
+        let user = User::get_by_uuid(user_uuid, db).await?;
+        UserService::ensure_can_login(&user)?;
+        if !user.mfa_passkey_enabled {
+            return Err(UserError::MfaNotEnrolled.into());
+        }
+
+        let passkey_user_uuid =
+            Self::finish_authentication(state, ceremony_id, credential, db)
+                .await?;
+
+        if passkey_user_uuid != user_uuid {
+            return Err(UserError::InvalidMfaCode.into());
+        }
+
+        MfaService::consume_token(&sub, db).await?;
+
+        User::update_last_login(user.id, db).await?;
+
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoys_are_stable_per_email() {
+        let key = [1; 32];
+
+        let id = decoy_credential_id(&key, "user@example.com");
+        assert_eq!(id, decoy_credential_id(&key, " User@Example.com"));
+        assert_ne!(id, decoy_credential_id(&key, "other@example.com"));
+        assert_ne!(id, decoy_credential_id(&[2; 32], "user@example.com"));
+    }
+}
@@ -0,0 +1,59 @@
+use uuid::Uuid;
+
+use crate::{
+    dto::role::{Grants, Role},
+    error::{ErrorRepr, ResultRepr},
+    service::user::UserService,
+    DbConn,
+};
+
+pub(crate) struct RoleService;
+
+impl RoleService {
+    pub(crate) async fn grants(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<Grants> {
+        let grants = Grants::get_by_user_uuid(user_uuid, db).await?;
+
+        Ok(grants)
+    }
+
+    pub(crate) async fn list(db: &DbConn) -> ResultRepr<Vec<Role>> {
+        let roles = Role::list(db).await?;
+
+        Ok(roles)
+    }
+
+    pub(crate) async fn assign(
+        uuid: Uuid,
+        role: &str,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        // A missing user is not found rather than a foreign key conflict
+        UserService::get_by_uuid(uuid, db).await?;
+        let role = Role::get_by_name(role, db).await?;
+
+        role.assign(uuid, db).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn unassign(
+        admin_uuid: Uuid,
+        uuid: Uuid,
+        role: &str,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        // Admins could lock themselves out
+        if admin_uuid == uuid {
+            return Err(ErrorRepr::Forbidden);
+        }
+
+        let role = Role::get_by_name(role, db).await?;
+
+        role.unassign(uuid, db).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{
+    config::constant::TRUSTED_DEVICE_TIMEOUT,
+    dto::{
+        auth::RefreshToken,
+        session::{SessionsPayload, SubTrustedDeviceToken, TrustedDevice},
+        user::User,
+    },
+    error::ResultRepr,
+    util::{
+        jwt::{Claims, ClaimsEncoded},
+        now_utc,
+    },
+    DbConn,
+};
+
+pub(crate) struct SessionService;
+
+impl SessionService {
+    pub(crate) async fn list(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<SessionsPayload> {
+        let sessions = RefreshToken::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let trusted_devices = TrustedDevice::get_by_user_uuid(user_uuid, db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(SessionsPayload {
+            sessions,
+            trusted_devices,
+        })
+    }
+
+    pub(crate) async fn revoke_session(
+        user_uuid: Uuid,
+        id: i32,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        RefreshToken::drop_by_id(id, user_uuid, db).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn revoke_trusted_device(
+        user_uuid: Uuid,
+        uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        TrustedDevice::drop_by_uuid(uuid, user_uuid, db).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn revoke_trusted_devices(
+        user_uuid: Uuid,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        TrustedDevice::drop_by_user_uuid(user_uuid, db).await?;
+
+        Ok(())
+    }
+
+    /// Remembers the device the second factor was just verified on
+    pub(crate) async fn trust_device(
+        user: &User,
+        name: Option<String>,
+        db: &DbConn,
+    ) -> ResultRepr<ClaimsEncoded<SubTrustedDeviceToken>> {
+        let expiry_date =
+            now_utc() + Duration::from_secs(TRUSTED_DEVICE_TIMEOUT);
+        let trusted_device =
+            TrustedDevice::new(user.uuid, name, expiry_date, db).await?;
+
+        let claims = Claims::with_expiry(
+            SubTrustedDeviceToken::new(
+                trusted_device.uuid,
+                user.uuid,
+                user.security_stamp,
+            ),
+            trusted_device.expiry_date.assume_utc(),
+        )?;
+
+        Ok(claims)
+    }
+
+    /// Whether the token lets this login skip the second factor, a stale or
+    /// invalid token just means the second factor is asked again
+    pub(crate) async fn is_trusted_device(
+        user: &User,
+        trusted_device_token: ClaimsEncoded<SubTrustedDeviceToken>,
+        db: &DbConn,
+    ) -> ResultRepr<bool> {
+        let Ok(claims) = trusted_device_token.decode() else {
+            return Ok(false);
+        };
+        let sub = claims.sub();
+
+        if sub.user_uuid != user.uuid
+            || sub.security_stamp != user.security_stamp
+        {
+            return Ok(false);
+        }
+
+        let trusted =
+            TrustedDevice::update_used(sub.device_uuid, user.uuid, db).await?;
+
+        Ok(trusted)
+    }
+}
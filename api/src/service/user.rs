@@ -1,16 +1,48 @@
-use std::time::Duration;
+use std::{net::IpAddr, time::Duration};
 
+use time::PrimitiveDateTime;
 use uuid::Uuid;
 
 use crate::{
+    config::{
+        constant::{
+            EMAIL_VERIFICATION_RESEND_COOLDOWN,
+            EMAIL_VERIFICATION_TOKEN_TIMEOUT, MAGIC_LINK_RESEND_COOLDOWN,
+            MAGIC_LINK_TOKEN_TIMEOUT, PASSWORD_RESET_RESEND_COOLDOWN,
+            PASSWORD_RESET_TOKEN_TIMEOUT, REAUTHENTICATION_TIMEOUT,
+        },
+        env::{
+            EmailVerificationPolicy, EMAIL_VERIFICATION_POLICY, FRONTEND_URL,
+        },
+    },
+    db::error::DbError,
     dto::{
-        auth::RefreshToken,
-        user::{LoginUserInput, RegisterUserInput, UpdateUserInput, User},
+        auth::{
+            LoginStep, OneTimeToken, RefreshToken, ResetPasswordInput,
+            SubAccesToken, SubMagicLinkToken, TokenPurpose,
+        },
+        mfa::MfaMethod,
+        role::UsersManage,
+        session::SubTrustedDeviceToken,
+        user::{
+            AccountStatus, LoginUserInput, PublicUserPayload,
+            RegisterUserInput, UpdateUserInput, User, UserIdentifier,
+        },
     },
     error::{ErrorRepr, ResultRepr, UserError},
+    mail::{Mail, Mailer},
+    middleware::locale::current_locale,
+    service::{
+        avatar::AvatarService, mfa::MfaService, role::RoleService,
+        session::SessionService,
+    },
+    storage::Storage,
     util::{
-        encryption::{hash_password, verify_password},
+        encryption::{hash_password, verify_password, DUMMY_PASSWORD_HASH},
+        jwt::{Claims, ClaimsEncoded, Decoded},
         now_utc,
+        throttle::LoginThrottle,
+        token::{generate_token, hash_token},
     },
     DbConn,
 };
@@ -36,11 +68,66 @@ impl UserService {
         token: Uuid,
         db: &DbConn,
     ) -> ResultRepr<(RefreshToken, User)> {
-        let res = RefreshToken::get_user_by_token(token, db).await?;
+        // A revoked session is an invalid token, not a missing resource
+        let res = RefreshToken::get_user_by_token(token, db).await.map_err(
+            |err| match err {
+                DbError::NoResult => ErrorRepr::User(UserError::InvalidToken),
+                err => err.into(),
+            },
+        )?;
+
+        Self::ensure_active(&res.1)?;
 
         Ok(res)
     }
 
+    /// Rejects users whose account an admin took away, suspensions lift by
+    /// themselves once they end
+    pub(crate) fn ensure_active(user: &User) -> ResultRepr<()> {
+        let reason = user.status_reason.clone();
+        let err = match user.status {
+            AccountStatus::Active => return Ok(()),
+            AccountStatus::Suspended
+                if user
+                    .suspended_until
+                    .is_some_and(|until| until <= now_utc()) =>
+            {
+                return Ok(());
+            }
+            AccountStatus::Disabled => UserError::AccountDisabled(reason),
+            AccountStatus::Suspended => {
+                UserError::AccountSuspended(reason, user.suspended_until)
+            }
+            AccountStatus::Banned => UserError::AccountBanned(reason),
+        };
+
+        Err(ErrorRepr::User(err))
+    }
+
+    /// Rejects sessions of a deleted account, logging in isn't checked
+    /// since that's how the deletion is cancelled
+    pub(crate) fn ensure_not_deleted(user: &User) -> ResultRepr<()> {
+        if user.deleted_at.is_some() {
+            return Err(ErrorRepr::User(UserError::AccountDeleted));
+        }
+
+        Ok(())
+    }
+
+    /// Every way of logging in goes through here, after the factor was
+    /// verified so failures don't reveal anything about the account
+    pub(crate) fn ensure_can_login(user: &User) -> ResultRepr<()> {
+        Self::ensure_active(user)?;
+
+        if *EMAIL_VERIFICATION_POLICY == EmailVerificationPolicy::Required
+            && user.email_verified_at.is_none()
+        {
+            return Err(ErrorRepr::User(UserError::EmailNotVerified));
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn get_by_uuid(
         uuid: Uuid,
         db: &DbConn,
@@ -50,16 +137,67 @@ impl UserService {
         Ok(res)
     }
 
+    /// Public view of a user, `caller` is who is asking if anyone is logged
+    /// in. The email is only shown to the user and to user managers
+    pub(crate) async fn profile(
+        identifier: UserIdentifier,
+        caller: Option<Uuid>,
+        db: &DbConn,
+    ) -> ResultRepr<PublicUserPayload> {
+        let user = User::get_visible_by_identifier(identifier, db)
+            .await?
+            .ok_or(DbError::NoResult)?;
+        let show_email = match caller {
+            Some(caller) if caller == user.uuid => true,
+            Some(caller) => RoleService::grants(caller, db)
+                .await?
+                .allows::<UsersManage>(),
+            None => false,
+        };
+
+        Ok(PublicUserPayload::new(user, show_email))
+    }
+
+    /// Updates the logged in user, sensitive changes need reauthentication
     pub(crate) async fn update_by_uuid(
-        uuid: Uuid,
+        claims: &Decoded<SubAccesToken>,
         mut update_user_input: UpdateUserInput,
+        throttle: &LoginThrottle,
+        mailer: &dyn Mailer,
         db: &DbConn,
     ) -> ResultRepr<()> {
+        let current_password = update_user_input.current_password.take();
+
         // Checks if all fields are `None`
         if update_user_input == Default::default() {
             return Ok(());
         }
 
+        if update_user_input.is_sensitive() {
+            Self::reauthenticate(claims, current_password, throttle, db)
+                .await?;
+        }
+
+        Self::apply_update(
+            claims.sub.user_uuid,
+            update_user_input,
+            claims.sub.session,
+            mailer,
+            db,
+        )
+        .await
+    }
+
+    /// Updates the user without asking for reauthentication, the caller is
+    /// responsible for checking it's allowed. A new password ends every
+    /// session but `keep_session`
+    pub(crate) async fn apply_update(
+        uuid: Uuid,
+        mut update_user_input: UpdateUserInput,
+        keep_session: Option<i32>,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
         // Hash password if not `None`/`Some(None)`
         let password = if let Some(password) = update_user_input.password {
             let password = if let Some(password) = password {
@@ -74,33 +212,280 @@ impl UserService {
             None
         };
 
+        let password_changed = password.is_some();
         update_user_input.password = password;
+        let email_changed = update_user_input.profile.email.is_some();
+
+        User::update_by_uuid(uuid, update_user_input, db)
+            .await
+            .map_err(Self::conflict)?;
+
+        // Sessions of whoever knew the old password may not outlive it
+        if password_changed {
+            RefreshToken::drop_by_user_uuid_except(uuid, keep_session, db)
+                .await?;
+        }
+
+        // The email did change, a new link can be requested when this fails
+        if email_changed {
+            let user = User::get_by_uuid(uuid, db).await?;
+            let res = Self::send_email_verification(&user, mailer, db).await;
+            if let Err(err) = res {
+                tracing::error!("failed to send email verification: {:?}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the user is who they claim to be before a sensitive change,
+    /// by their current password or, for passwordless accounts, a recently
+    /// issued access token
+    pub(crate) async fn reauthenticate(
+        claims: &Decoded<SubAccesToken>,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let user = User::get_by_uuid(claims.sub.user_uuid, db).await?;
+
+        match user.password {
+            Some(password_hash) => {
+                let current_password = current_password
+                    .ok_or(UserError::ReauthenticationRequired)?;
 
-        User::update_by_uuid(uuid, update_user_input, db).await?;
+                // Else a stolen access token could guess the password here
+                throttle.check_account(&user.email)?;
+                if !verify_password(current_password, password_hash).await? {
+                    throttle.record_account_failure(&user.email);
+                    return Err(ErrorRepr::User(
+                        UserError::ReauthenticationFailed,
+                    ));
+                }
+                throttle.record_success(&user.email);
+            }
+            None => {
+                // A login on another device doesn't vouch for this session
+                let timeout = Duration::from_secs(REAUTHENTICATION_TIMEOUT);
+                if now_utc() - claims.issued_at() > timeout {
+                    return Err(ErrorRepr::User(
+                        UserError::ReauthenticationRequired,
+                    ));
+                }
+            }
+        }
 
         Ok(())
     }
 
     pub(crate) async fn login(
         input: LoginUserInput,
+        ip: IpAddr,
+        throttle: &LoginThrottle,
+        mailer: &dyn Mailer,
         db: &DbConn,
-    ) -> ResultRepr<Uuid> {
+    ) -> ResultRepr<LoginStep> {
+        throttle.check(&input.email, ip)?;
+
         let password = input.password.ok_or(UserError::PasswordRequired)?;
 
-        let result =
-            User::get_id_uuid_password_by_email(input.email, db).await?;
+        let user = User::get_by_email(input.email.clone(), db).await?;
+        let user = match Self::verify_credentials(user, password).await {
+            Ok(user) => user,
+            Err(ErrorRepr::User(
+                err @ (UserError::NotFound
+                | UserError::NoPassword
+                | UserError::PasswordWrong),
+            )) => {
+                throttle.record_failure(&input.email, ip);
+                return Err(err.into());
+            }
+            Err(err) => return Err(err),
+        };
+        throttle.record_success(&input.email);
 
-        let (id, uuid, password_hash) = result.ok_or(UserError::NotFound)?;
+        Self::first_factor_verified(
+            user,
+            input.trusted_device_token,
+            mailer,
+            db,
+        )
+        .await
+    }
 
-        let password_hash = password_hash.ok_or(UserError::NoPassword)?;
+    async fn verify_credentials(
+        user: Option<User>,
+        password: String,
+    ) -> ResultRepr<User> {
+        // Always verify a hash, failing early would reveal which emails exist
+        let password_hash = user
+            .as_ref()
+            .and_then(|user| user.password.clone())
+            .unwrap_or_else(|| DUMMY_PASSWORD_HASH.clone());
+        let valid = verify_password(password, password_hash).await?;
 
-        if verify_password(password, password_hash).await? {
-            User::update_last_login(id, db).await?;
+        let user = user.ok_or(UserError::NotFound)?;
+        if user.password.is_none() {
+            return Err(ErrorRepr::User(UserError::NoPassword));
+        }
+        if !valid {
+            return Err(ErrorRepr::User(UserError::PasswordWrong));
+        }
 
-            Ok(uuid)
-        } else {
-            Err(ErrorRepr::User(UserError::PasswordWrong))
+        Ok(user)
+    }
+
+    /// Completes the login or requests a second factor when 2FA is enabled
+    async fn first_factor_verified(
+        user: User,
+        trusted_device_token: Option<ClaimsEncoded<SubTrustedDeviceToken>>,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<LoginStep> {
+        Self::ensure_can_login(&user)?;
+
+        let trusted_device = match trusted_device_token {
+            Some(token) => {
+                SessionService::is_trusted_device(&user, token, db).await?
+            }
+            None => false,
+        };
+
+        let methods = MfaService::methods(&user, db).await?;
+        if !methods.is_empty() && !trusted_device {
+            if methods.contains(&MfaMethod::Email) {
+                MfaService::send_email_code(&user, mailer, db).await?;
+            }
+
+            return Ok(LoginStep::MfaRequired(user.uuid, methods));
         }
+
+        User::update_last_login(user.id, db).await?;
+
+        Ok(LoginStep::Complete(Box::new(user)))
+    }
+
+    /// Mails a single-use login link when a user with this email exists and
+    /// didn't get one in the last `MAGIC_LINK_RESEND_COOLDOWN`
+    pub(crate) async fn request_magic_link(
+        email: String,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let Some(user) = User::get_by_email(email, db).await? else {
+            return Ok(());
+        };
+
+        let purpose = TokenPurpose::MagicLink;
+        if Self::issued_recently(&user, purpose, MAGIC_LINK_RESEND_COOLDOWN, db)
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::issue_and_mail(
+            &user,
+            purpose,
+            MAGIC_LINK_TOKEN_TIMEOUT,
+            |token| {
+                let claims = Claims::new(SubMagicLinkToken::new(token))?;
+                let link = format!(
+                    "{}/login/magic?token={}",
+                    *FRONTEND_URL,
+                    claims.as_str()
+                );
+
+                Ok(Mail {
+                    to: user.email.clone(),
+                    subject: "Your login link".to_string(),
+                    body: format!(
+                        "Log in within {} minutes using {}",
+                        MAGIC_LINK_TOKEN_TIMEOUT / 60,
+                        link
+                    ),
+                })
+            },
+            mailer,
+            db,
+        )
+        .await
+    }
+
+    /// Whether a token for `purpose` was issued in the last `cooldown`
+    /// seconds, requests within it are silently dropped so they can't be used
+    /// to flood a mailbox
+    async fn issued_recently(
+        user: &User,
+        purpose: TokenPurpose,
+        cooldown: u64,
+        db: &DbConn,
+    ) -> ResultRepr<bool> {
+        let since = now_utc() - Duration::from_secs(cooldown);
+        let issued =
+            OneTimeToken::issued_since(user.uuid, purpose, since, db).await?;
+
+        Ok(issued)
+    }
+
+    /// Issues a new single-use token for `purpose` and mails it, `mail`
+    /// builds the message around the plain token
+    async fn issue_and_mail(
+        user: &User,
+        purpose: TokenPurpose,
+        timeout: u64,
+        mail: impl FnOnce(String) -> ResultRepr<Mail>,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        // Only the most recently requested link stays valid
+        OneTimeToken::drop_by_user_uuid(user.uuid, purpose, db).await?;
+
+        let token = generate_token();
+        let expiry_date = now_utc() + Duration::from_secs(timeout);
+
+        OneTimeToken::new(
+            user.uuid,
+            purpose,
+            hash_token(&token),
+            expiry_date,
+            db,
+        )
+        .await?;
+
+        mailer.send(mail(token)?).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn login_magic_link(
+        claims: ClaimsEncoded<SubMagicLinkToken>,
+        trusted_device_token: Option<ClaimsEncoded<SubTrustedDeviceToken>>,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<LoginStep> {
+        let token = claims
+            .decode()
+            .map_err(|_| UserError::InvalidToken)?
+            .sub()
+            .token;
+
+        let one_time_token = OneTimeToken::consume(
+            hash_token(&token),
+            TokenPurpose::MagicLink,
+            db,
+        )
+        .await?
+        .ok_or(UserError::InvalidToken)?;
+
+        let user = User::get_by_uuid(one_time_token.user_uuid, db).await?;
+
+        // Opening the link proves access to the mailbox
+        if user.email_verified_at.is_none() {
+            User::set_email_verified(user.uuid, db).await?;
+        }
+
+        Self::first_factor_verified(user, trusted_device_token, mailer, db)
+            .await
     }
 
     pub(crate) async fn logout(token: Uuid, db: &DbConn) -> ResultRepr<()> {
@@ -109,12 +494,189 @@ impl UserService {
         Ok(())
     }
 
+    /// Deletes the account once the grace period is over, ending every
+    /// session right away
+    pub(crate) async fn delete(
+        claims: &Decoded<SubAccesToken>,
+        current_password: Option<String>,
+        throttle: &LoginThrottle,
+        db: &DbConn,
+    ) -> ResultRepr<PrimitiveDateTime> {
+        Self::reauthenticate(claims, current_password, throttle, db).await?;
+
+        let uuid = claims.sub.user_uuid;
+
+        let purge_at = User::mark_deleted(uuid, db).await?;
+        RefreshToken::drop_by_user_uuid(uuid, db).await?;
+
+        Ok(purge_at)
+    }
+
+    pub(crate) async fn purge_deleted(
+        storage: &dyn Storage,
+        db: &DbConn,
+    ) -> ResultRepr<u64> {
+        let (purged, avatar_urls) = User::purge_deleted(db).await?;
+
+        for avatar_url in avatar_urls {
+            AvatarService::release(&avatar_url, storage, db).await?;
+        }
+
+        Ok(purged)
+    }
+
+    /// Mails a password reset link when a user with this email exists and
+    /// didn't get one in the last `PASSWORD_RESET_RESEND_COOLDOWN`
+    pub(crate) async fn forgot_password(
+        email: String,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let Some(user) = User::get_by_email(email, db).await? else {
+            return Ok(());
+        };
+
+        let purpose = TokenPurpose::PasswordReset;
+        if Self::issued_recently(
+            &user,
+            purpose,
+            PASSWORD_RESET_RESEND_COOLDOWN,
+            db,
+        )
+        .await?
+        {
+            return Ok(());
+        }
+
+        Self::issue_and_mail(
+            &user,
+            purpose,
+            PASSWORD_RESET_TOKEN_TIMEOUT,
+            |token| {
+                let link =
+                    format!("{}/password/reset?token={}", *FRONTEND_URL, token);
+
+                Ok(Mail {
+                    to: user.email.clone(),
+                    subject: "Reset your password".to_string(),
+                    body: format!(
+                        "Reset your password within {} minutes using {}",
+                        PASSWORD_RESET_TOKEN_TIMEOUT / 60,
+                        link
+                    ),
+                })
+            },
+            mailer,
+            db,
+        )
+        .await
+    }
+
+    pub(crate) async fn reset_password(
+        input: ResetPasswordInput,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let one_time_token = OneTimeToken::consume(
+            hash_token(&input.token),
+            TokenPurpose::PasswordReset,
+            db,
+        )
+        .await?
+        .ok_or(UserError::InvalidToken)?;
+
+        let update_user_input = UpdateUserInput {
+            password: Some(Some(hash_password(input.password).await?)),
+            ..Default::default()
+        };
+        User::update_by_uuid(one_time_token.user_uuid, update_user_input, db)
+            .await?;
+
+        // Sessions of whoever knew the old password may not outlive it
+        RefreshToken::drop_by_user_uuid(one_time_token.user_uuid, db).await?;
+
+        Ok(())
+    }
+
+    /// Mails a link to verify the current email of the user
+    pub(crate) async fn send_email_verification(
+        user: &User,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        Self::issue_and_mail(
+            user,
+            TokenPurpose::EmailVerification,
+            EMAIL_VERIFICATION_TOKEN_TIMEOUT,
+            |token| {
+                let link =
+                    format!("{}/verify-email?token={}", *FRONTEND_URL, token);
+
+                Ok(Mail {
+                    to: user.email.clone(),
+                    subject: "Verify your email".to_string(),
+                    body: format!(
+                        "Verify your email within {} hours using {}",
+                        EMAIL_VERIFICATION_TOKEN_TIMEOUT / (60 * 60),
+                        link
+                    ),
+                })
+            },
+            mailer,
+            db,
+        )
+        .await
+    }
+
+    /// Mails a new link when an unverified user with this email exists and
+    /// didn't get one in the last `EMAIL_VERIFICATION_RESEND_COOLDOWN`
+    pub(crate) async fn resend_email_verification(
+        email: String,
+        mailer: &dyn Mailer,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let Some(user) = User::get_by_email(email, db).await? else {
+            return Ok(());
+        };
+
+        if user.email_verified_at.is_some() {
+            return Ok(());
+        }
+
+        if Self::issued_recently(
+            &user,
+            TokenPurpose::EmailVerification,
+            EMAIL_VERIFICATION_RESEND_COOLDOWN,
+            db,
+        )
+        .await?
+        {
+            return Ok(());
+        }
+
+        Self::send_email_verification(&user, mailer, db).await
+    }
+
+    pub(crate) async fn verify_email(
+        token: String,
+        db: &DbConn,
+    ) -> ResultRepr<()> {
+        let one_time_token = OneTimeToken::consume(
+            hash_token(&token),
+            TokenPurpose::EmailVerification,
+            db,
+        )
+        .await?
+        .ok_or(UserError::InvalidToken)?;
+
+        User::set_email_verified(one_time_token.user_uuid, db).await?;
+
+        Ok(())
+    }
+
     pub(crate) async fn register_user(
         input: RegisterUserInput,
         db: &DbConn,
     ) -> ResultRepr<User> {
-        // TODO: Check if displayname/email already exists
-
         let now = now_utc();
 
         let password = if let Some(password) = input.password {
@@ -127,13 +689,153 @@ impl UserService {
             display_name: input.display_name,
             email: input.email,
             password,
+            locale: input.locale.unwrap_or_else(current_locale),
             uuid: Uuid::new_v4(),
             created_at: now,
             updated_at: now,
             ..Default::default()
         };
-        let user = user.create(db).await?;
+        // Duplicates are caught by the unique constraints, a check up front
+        // could race with another request
+        let user = user.create(db).await.map_err(Self::conflict)?;
 
         Ok(user)
     }
+
+    /// Names the input field behind a unique violation on the user table
+    fn conflict(err: DbError) -> ErrorRepr {
+        let field = match err {
+            DbError::UniqueViolation(Some(ref column)) => match column.as_str()
+            {
+                "email" => "email",
+                "displayname" => "display_name",
+                _ => return err.into(),
+            },
+            err => return err.into(),
+        };
+
+        ErrorRepr::User(UserError::Conflict(vec![field]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::encryption::VERIFICATIONS;
+
+    async fn verifications_for(
+        user: Option<User>,
+        password: &str,
+    ) -> (usize, ResultRepr<User>) {
+        let before = VERIFICATIONS.with(|verifications| verifications.get());
+        let result =
+            UserService::verify_credentials(user, password.to_string()).await;
+        let after = VERIFICATIONS.with(|verifications| verifications.get());
+
+        (after - before, result)
+    }
+
+    fn user(password: Option<String>) -> Option<User> {
+        Some(User {
+            password,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn every_login_verifies_a_hash() {
+        let password_hash =
+            hash_password("password".to_string()).await.unwrap();
+
+        let (verifications, result) = verifications_for(None, "password").await;
+        assert_eq!(verifications, 1);
+        assert!(matches!(result, Err(ErrorRepr::User(UserError::NotFound))));
+
+        let (verifications, result) =
+            verifications_for(user(None), "password").await;
+        assert_eq!(verifications, 1);
+        assert!(matches!(
+            result,
+            Err(ErrorRepr::User(UserError::NoPassword))
+        ));
+
+        let (verifications, result) =
+            verifications_for(user(Some(password_hash.clone())), "wrong").await;
+        assert_eq!(verifications, 1);
+        assert!(matches!(
+            result,
+            Err(ErrorRepr::User(UserError::PasswordWrong))
+        ));
+
+        let (verifications, result) =
+            verifications_for(user(Some(password_hash)), "password").await;
+        assert_eq!(verifications, 1);
+        assert!(result.is_ok());
+    }
+
+    fn with_status(
+        status: AccountStatus,
+        suspended_until: Option<PrimitiveDateTime>,
+    ) -> User {
+        User {
+            status,
+            status_reason: Some("reason".to_string()),
+            suspended_until,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_active_users_pass() {
+        let user = with_status(AccountStatus::Active, None);
+        assert!(UserService::ensure_active(&user).is_ok());
+
+        let user = with_status(AccountStatus::Disabled, None);
+        assert!(matches!(
+            UserService::ensure_active(&user),
+            Err(ErrorRepr::User(UserError::AccountDisabled(Some(_))))
+        ));
+
+        let user = with_status(AccountStatus::Banned, None);
+        assert!(matches!(
+            UserService::ensure_active(&user),
+            Err(ErrorRepr::User(UserError::AccountBanned(Some(_))))
+        ));
+    }
+
+    #[test]
+    fn suspensions_lift_once_they_end() {
+        let until = now_utc() + Duration::from_secs(60);
+        let user = with_status(AccountStatus::Suspended, Some(until));
+        assert!(matches!(
+            UserService::ensure_active(&user),
+            Err(ErrorRepr::User(UserError::AccountSuspended(_, Some(_))))
+        ));
+
+        // Without an end it lasts until an admin lifts it
+        let user = with_status(AccountStatus::Suspended, None);
+        assert!(matches!(
+            UserService::ensure_active(&user),
+            Err(ErrorRepr::User(UserError::AccountSuspended(_, None)))
+        ));
+
+        let until = now_utc() - Duration::from_secs(60);
+        let user = with_status(AccountStatus::Suspended, Some(until));
+        assert!(UserService::ensure_active(&user).is_ok());
+    }
+
+    #[test]
+    fn deleted_users_are_rejected() {
+        let user = User::default();
+        assert!(UserService::ensure_not_deleted(&user).is_ok());
+
+        let user = User {
+            deleted_at: Some(now_utc()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            UserService::ensure_not_deleted(&user),
+            Err(ErrorRepr::User(UserError::AccountDeleted))
+        ));
+    }
 }
@@ -0,0 +1,146 @@
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use axum::async_trait;
+use thiserror::Error as ErrorTrait;
+use uuid::Uuid;
+
+#[derive(Debug, ErrorTrait)]
+#[error("storage failed: {0}")]
+pub struct StorageError(pub String);
+
+/// Keeps the files uploaded to the api, implement this to plug in another
+/// store (e.g. S3). Keys are relative paths like `avatars/<hash>/256.png`
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Stores files in a directory on the local filesystem
+#[derive(Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Refuses keys that could escape the root
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(StorageError(format!("invalid key {key:?}")));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| StorageError(err.to_string()))?;
+        }
+
+        // Readers never see a partially written file, and concurrent writers
+        // of the same key don't share a temporary file
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| StorageError(format!("invalid key {key:?}")))?;
+        let tmp_path =
+            path.with_file_name(format!("{file_name}.{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&tmp_path, bytes)
+            .await
+            .map_err(|err| StorageError(err.to_string()))?;
+        if let Err(err) = tokio::fs::rename(&tmp_path, &path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(StorageError(err.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError(err.to_string())),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(StorageError(err.to_string())),
+        }
+
+        // `put` creates the directories, only fails when others are left
+        if let Some(parent) =
+            path.parent().filter(|parent| *parent != self.root)
+        {
+            let _ = tokio::fs::remove_dir(parent).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_stay_inside_the_root() {
+        let storage = LocalStorage::new("/srv/storage");
+
+        assert_eq!(
+            storage.path("avatars/abc/256.png").unwrap(),
+            Path::new("/srv/storage/avatars/abc/256.png")
+        );
+        assert!(storage.path("../etc/passwd").is_err());
+        assert!(storage.path("avatars/../../etc/passwd").is_err());
+        assert!(storage.path("/etc/passwd").is_err());
+        assert!(storage.path("./avatars/abc").is_err());
+    }
+
+    #[tokio::test]
+    async fn put_get_delete() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let storage = LocalStorage::new(&root);
+
+        storage
+            .put("avatars/abc/64.png", vec![1, 2, 3])
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get("avatars/abc/64.png").await.unwrap(),
+            Some(vec![1, 2, 3])
+        );
+
+        storage.delete("avatars/abc/64.png").await.unwrap();
+        storage.delete("avatars/abc/64.png").await.unwrap();
+        assert_eq!(storage.get("avatars/abc/64.png").await.unwrap(), None);
+        assert!(!root.join("avatars/abc").exists());
+
+        let _ = tokio::fs::remove_dir_all(root).await;
+    }
+}
@@ -1,17 +1,66 @@
+use chrono_tz::Tz;
 use time::{OffsetDateTime, PrimitiveDateTime};
-use validator::Validate;
+use url::Url;
+use validator::{Validate, ValidationError};
 
 use crate::error::ResultRepr;
 
+pub(crate) mod avatar;
+pub(crate) mod ceremony;
+pub(crate) mod datetime;
 pub(crate) mod encryption;
 pub(crate) mod jwt;
+pub(crate) mod throttle;
+pub(crate) mod token;
+pub(crate) mod totp;
 
 pub(crate) fn validate_payload<T: Validate>(payload: &T) -> ResultRepr<()> {
     Ok(payload.validate()?)
 }
 
+/// Only accepts IANA timezone names, e.g. `Europe/Brussels`
+pub(crate) fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    timezone
+        .parse::<Tz>()
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("timezone"))
+}
+
+/// Only accepts absolute `http` and `https` urls, `#[validate(url)]` also
+/// lets through schemes like `javascript:`
+pub(crate) fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    match Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ValidationError::new("http_url")),
+    }
+}
+
 pub fn now_utc() -> PrimitiveDateTime {
     let now = OffsetDateTime::now_utc();
 
     PrimitiveDateTime::new(now.date(), now.time())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timezone_must_be_iana() {
+        assert!(validate_timezone("Europe/Brussels").is_ok());
+        assert!(validate_timezone("UTC").is_ok());
+        assert!(validate_timezone("Europe/Atlantis").is_err());
+        assert!(validate_timezone("+02:00").is_err());
+        assert!(validate_timezone("").is_err());
+    }
+
+    #[test]
+    fn url_must_be_http() {
+        assert!(validate_http_url("https://example.com/me").is_ok());
+        assert!(validate_http_url("http://example.com").is_ok());
+        assert!(validate_http_url("javascript:alert(1)").is_err());
+        assert!(validate_http_url("data:text/html,hi").is_err());
+        assert!(validate_http_url("ftp://example.com").is_err());
+        assert!(validate_http_url("example.com").is_err());
+    }
+}
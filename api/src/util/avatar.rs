@@ -0,0 +1,98 @@
+use std::{io::Cursor, sync::Arc};
+
+use image::{
+    imageops::FilterType,
+    io::{Limits, Reader},
+    ImageFormat, ImageOutputFormat,
+};
+use lazy_static::lazy_static;
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::constant::{
+        AVATAR_MAX_ALLOC, AVATAR_MAX_DECODES, AVATAR_MAX_DIMENSION,
+        AVATAR_SIZES,
+    },
+    error::{ErrorRepr, ResultRepr},
+};
+
+lazy_static! {
+    static ref DECODES: Arc<Semaphore> =
+        Arc::new(Semaphore::new(AVATAR_MAX_DECODES));
+}
+
+/// `thumbnails` on the blocking threads, rejected instead of queued when
+/// `AVATAR_MAX_DECODES` uploads are already being decoded
+pub(crate) async fn spawn_thumbnails(
+    bytes: Vec<u8>,
+) -> ResultRepr<Vec<(u32, Vec<u8>)>> {
+    let permit = DECODES
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| ErrorRepr::AvatarDecodesFull)?;
+
+    // The permit goes along, a cancelled request doesn't stop the decode
+    tokio::task::spawn_blocking(move || {
+        let thumbnails = thumbnails(&bytes);
+        drop(permit);
+
+        thumbnails
+    })
+    .await?
+}
+
+/// Square PNG thumbnails in every size of `AVATAR_SIZES`, re-encoding drops
+/// the EXIF and any other metadata of the upload
+pub(crate) fn thumbnails(bytes: &[u8]) -> ResultRepr<Vec<(u32, Vec<u8>)>> {
+    // Sniffed from the bytes, the content type of the upload can't be trusted
+    let format = match image::guess_format(bytes) {
+        Ok(
+            format @ (ImageFormat::Png
+            | ImageFormat::Jpeg
+            | ImageFormat::Gif
+            | ImageFormat::WebP),
+        ) => format,
+        _ => return Err(ErrorRepr::UnsupportedImage),
+    };
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(AVATAR_MAX_DIMENSION);
+    limits.max_image_height = Some(AVATAR_MAX_DIMENSION);
+    limits.max_alloc = Some(AVATAR_MAX_ALLOC);
+
+    let mut reader = Reader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let image = reader.decode()?;
+
+    AVATAR_SIZES
+        .iter()
+        .map(|&size| {
+            let mut png = Cursor::new(Vec::new());
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut png, ImageOutputFormat::Png)?;
+
+            Ok((size, png.into_inner()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn decodes_are_rejected_when_saturated() {
+        let permits = DECODES
+            .clone()
+            .try_acquire_many_owned(AVATAR_MAX_DECODES as u32)
+            .unwrap();
+
+        let result = spawn_thumbnails(Vec::new()).await;
+        assert!(matches!(result, Err(ErrorRepr::AvatarDecodesFull)));
+
+        drop(permits);
+        let result = spawn_thumbnails(Vec::new()).await;
+        assert!(matches!(result, Err(ErrorRepr::UnsupportedImage)));
+    }
+}
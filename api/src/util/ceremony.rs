@@ -0,0 +1,85 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+/// Keeps the server side state of a multi-step ceremony (e.g. WebAuthn)
+/// between requests, every state can be taken only once
+pub(crate) struct CeremonyStore<T> {
+    timeout: Duration,
+    capacity: usize,
+    ceremonies: Mutex<HashMap<Uuid, (Instant, T)>>,
+}
+
+impl<T> CeremonyStore<T> {
+    pub(crate) fn new(timeout: Duration, capacity: usize) -> Self {
+        Self {
+            timeout,
+            capacity,
+            ceremonies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// None when the store is full of ceremonies that are still ongoing
+    pub(crate) fn insert(&self, state: T) -> Option<Uuid> {
+        let id = Uuid::new_v4();
+        let now = Instant::now();
+
+        let mut ceremonies =
+            self.ceremonies.lock().expect("ceremony store poisoned");
+
+        // Forget abandoned ceremonies once they take up the room
+        if ceremonies.len() >= self.capacity {
+            ceremonies.retain(|_, (started, _)| {
+                now.duration_since(*started) < self.timeout
+            });
+        }
+        if ceremonies.len() >= self.capacity {
+            return None;
+        }
+        ceremonies.insert(id, (now, state));
+
+        Some(id)
+    }
+
+    pub(crate) fn take(&self, id: Uuid) -> Option<T> {
+        let (started, state) = self
+            .ceremonies
+            .lock()
+            .expect("ceremony store poisoned")
+            .remove(&id)?;
+
+        (started.elapsed() < self.timeout).then_some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ceremonies_are_taken_once() {
+        let store = CeremonyStore::new(Duration::from_secs(60), 10);
+
+        let id = store.insert("state").unwrap();
+
+        assert_eq!(store.take(id), Some("state"));
+        assert_eq!(store.take(id), None);
+    }
+
+    #[test]
+    fn full_store_only_makes_room_by_expiry() {
+        let store = CeremonyStore::new(Duration::from_secs(60), 2);
+        store.insert(1).unwrap();
+        store.insert(2).unwrap();
+        assert!(store.insert(3).is_none());
+
+        let store = CeremonyStore::new(Duration::ZERO, 2);
+        store.insert(1).unwrap();
+        store.insert(2).unwrap();
+        assert!(store.insert(3).is_some());
+    }
+}
@@ -0,0 +1,56 @@
+//! (De)serializes the UTC timestamps from the db as RFC 3339 strings, use
+//! with `#[serde(with = "...")]`
+
+pub(crate) mod rfc3339 {
+    use serde::{ser::Error, Serializer};
+    use time::{format_description::well_known::Rfc3339, PrimitiveDateTime};
+
+    pub(crate) fn serialize<S: Serializer>(
+        datetime: &PrimitiveDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let formatted = datetime
+            .assume_utc()
+            .format(&Rfc3339)
+            .map_err(S::Error::custom)?;
+
+        serializer.serialize_str(&formatted)
+    }
+
+    pub(crate) mod option {
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+        use time::{
+            format_description::well_known::Rfc3339, OffsetDateTime,
+            PrimitiveDateTime, UtcOffset,
+        };
+
+        pub(crate) fn serialize<S: Serializer>(
+            datetime: &Option<PrimitiveDateTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match datetime {
+                Some(datetime) => super::serialize(datetime, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        /// Other offsets are converted to UTC
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<PrimitiveDateTime>, D::Error> {
+            let Some(datetime) = Option::<String>::deserialize(deserializer)?
+            else {
+                return Ok(None);
+            };
+
+            let datetime = OffsetDateTime::parse(&datetime, &Rfc3339)
+                .map_err(D::Error::custom)?
+                .to_offset(UtcOffset::UTC);
+
+            Ok(Some(PrimitiveDateTime::new(
+                datetime.date(),
+                datetime.time(),
+            )))
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use argon2::{
     password_hash::{
         rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier,
@@ -5,8 +7,100 @@ use argon2::{
     },
     Argon2,
 };
+use lazy_static::lazy_static;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::{
+    config::env::{PASSWORD_HASHING_QUEUE_SIZE, PASSWORD_HASHING_THREADS},
+    error::{ErrorRepr, ResultRepr},
+};
+
+lazy_static! {
+    /// Verified against when there is no real hash, so a login for an unknown
+    /// or passwordless account takes as long as one with a wrong password
+    pub(crate) static ref DUMMY_PASSWORD_HASH: String = {
+        let salt = SaltString::generate(&mut OsRng);
+        let password = SaltString::generate(&mut OsRng);
+
+        argon2_config()
+            .hash_password(password.as_str().as_bytes(), &salt)
+            .expect("failed to hash the dummy password")
+            .to_string()
+    };
+    pub(crate) static ref HASHING_POOL: HashingPool = HashingPool::new(
+        PASSWORD_HASHING_THREADS.unwrap_or(0),
+        *PASSWORD_HASHING_QUEUE_SIZE,
+    );
+}
+
+#[cfg(test)]
+thread_local! {
+    pub(crate) static VERIFICATIONS: std::cell::Cell<usize> =
+        const { std::cell::Cell::new(0) };
+}
+
+/// Argon2 is expensive on purpose, so it gets its own threads and a bounded
+/// queue instead of starving or flooding the global rayon pool
+pub(crate) struct HashingPool {
+    pool: ThreadPool,
+    capacity: usize,
+    depth: AtomicUsize,
+}
+
+impl HashingPool {
+    /// Zero threads means one per core
+    fn new(threads: usize, capacity: usize) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("password-hashing-{index}"))
+            // Rayon aborts the process on a panic without a handler
+            .panic_handler(|_| tracing::error!("password hashing job panicked"))
+            .build()
+            .expect("failed to build the password hashing pool");
+
+        Self {
+            pool,
+            capacity,
+            depth: AtomicUsize::new(0),
+        }
+    }
+
+    /// Jobs that are queued or running
+    pub(crate) fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
 
-use crate::error::ResultRepr;
+    fn spawn<F>(&'static self, job: F) -> ResultRepr<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if self.depth.fetch_add(1, Ordering::AcqRel) >= self.capacity {
+            self.depth.fetch_sub(1, Ordering::AcqRel);
+            return Err(ErrorRepr::HashingPoolFull);
+        }
+
+        self.pool.spawn(move || {
+            // Released even when the job panics
+            let _slot = Slot(&self.depth);
+            job();
+        });
+
+        Ok(())
+    }
+}
+
+/// A taken place in the queue, given back on drop
+struct Slot<'a>(&'a AtomicUsize);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
 
 fn argon2_config<'a>() -> Argon2<'a> {
     Argon2::default()
@@ -16,7 +110,7 @@ pub(crate) async fn hash_password(password: String) -> ResultRepr<String> {
     // TODO: Add pepper
     let (send, recv) = tokio::sync::oneshot::channel();
 
-    rayon::spawn(move || {
+    HASHING_POOL.spawn(move || {
         let salt = SaltString::generate(&mut OsRng);
 
         let argon2 = argon2_config();
@@ -30,7 +124,7 @@ pub(crate) async fn hash_password(password: String) -> ResultRepr<String> {
         if error {
             tracing::error!("the receiver dropped");
         }
-    });
+    })?;
 
     Ok(recv.await??)
 }
@@ -39,9 +133,13 @@ pub(crate) async fn verify_password(
     password: String,
     hash: String,
 ) -> ResultRepr<bool> {
+    #[cfg(test)]
+    VERIFICATIONS
+        .with(|verifications| verifications.set(verifications.get() + 1));
+
     let (send, recv) = tokio::sync::oneshot::channel();
 
-    rayon::spawn(move || {
+    HASHING_POOL.spawn(move || {
         let result = match PasswordHash::new(&hash) {
             Ok(parsed_hash) => {
                 let result = Argon2::default()
@@ -56,7 +154,30 @@ pub(crate) async fn verify_password(
         if send.send(result).is_err() {
             tracing::error!("the receiver dropped");
         };
-    });
+    })?;
 
     Ok(recv.await??)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn panicking_job_releases_its_slot() {
+        let pool: &'static HashingPool =
+            Box::leak(Box::new(HashingPool::new(1, 1)));
+
+        pool.spawn(|| panic!("job failed")).unwrap();
+
+        let start = Instant::now();
+        while pool.depth() > 0 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(pool.spawn(|| {}).is_ok());
+    }
+}
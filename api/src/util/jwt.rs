@@ -2,7 +2,7 @@ use std::{marker::PhantomData, time::Duration};
 
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{OffsetDateTime, PrimitiveDateTime};
 
 use crate::error::ResultRepr;
 
@@ -34,10 +34,29 @@ pub(crate) struct Claims<T: ClaimsSubTrait, State = Decoded<T>> {
     _type: PhantomData<T>,
 }
 
+impl<T: ClaimsSubTrait> Decoded<T> {
+    /// Invalid timestamps are as old as can be
+    pub(crate) fn issued_at(&self) -> PrimitiveDateTime {
+        let iat = OffsetDateTime::from_unix_timestamp(self.iat)
+            .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+
+        PrimitiveDateTime::new(iat.date(), iat.time())
+    }
+}
+
 impl<T: ClaimsSubTrait> Claims<T> {
     pub(crate) fn new(claims: T) -> ResultRepr<Claims<T, Encoded>> {
+        let exp = OffsetDateTime::now_utc() + Duration::from_secs(T::DURATION);
+
+        Self::with_expiry(claims, exp)
+    }
+
+    /// For tokens backed by a db row, so both expire at the same time
+    pub(crate) fn with_expiry(
+        claims: T,
+        exp: OffsetDateTime,
+    ) -> ResultRepr<Claims<T, Encoded>> {
         let iat = OffsetDateTime::now_utc();
-        let exp = iat + Duration::from_secs(T::DURATION);
 
         let claim = Self {
             claims: Decoded {
@@ -73,10 +92,20 @@ impl<T: ClaimsSubTrait> Claims<T, Decoded<T>> {
     pub(crate) fn sub(self) -> T {
         self.claims.sub
     }
+
+    pub(crate) fn as_sub(&self) -> &T {
+        &self.claims.sub
+    }
 }
 
 impl<T: ClaimsSubTrait> Claims<T, Encoded> {
-    pub(crate) fn decode(self) -> ResultRepr<Claims<T, Decoded<T>>>
+    pub(crate) fn as_str(&self) -> &str {
+        &self.claims.0
+    }
+
+    pub(crate) fn decode(
+        self,
+    ) -> Result<Claims<T, Decoded<T>>, jsonwebtoken::errors::Error>
     where
         Decoded<T>: DeserializeOwned,
     {
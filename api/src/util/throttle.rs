@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::constant::{
+        ACCOUNT_FREE_LOGIN_ATTEMPTS, ACCOUNT_LOCKOUT_DURATION,
+        ACCOUNT_LOCKOUT_THRESHOLD, ACCOUNT_LOGIN_BACKOFF_MAX,
+        IP_FREE_LOGIN_ATTEMPTS, IP_LOGIN_BACKOFF_MAX, LOGIN_BACKOFF_BASE,
+        LOGIN_FAILURE_WINDOW, LOGIN_THROTTLE_PRUNE_INTERVAL,
+    },
+    error::UserError,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ThrottlePolicy {
+    /// Failures allowed before any delay is enforced
+    pub(crate) free_attempts: u32,
+    pub(crate) backoff_base: Duration,
+    pub(crate) backoff_max: Duration,
+    /// Failures after which the key is locked instead of delayed
+    pub(crate) lockout_threshold: Option<u32>,
+    pub(crate) lockout_duration: Duration,
+    /// Failures are forgotten when there has been none for this long
+    pub(crate) window: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Blocked {
+    Throttled(Duration),
+    Locked(Duration),
+}
+
+#[derive(Debug)]
+struct Failures {
+    count: u32,
+    last_failure: Instant,
+    blocked_until: Instant,
+    locked: bool,
+}
+
+impl Failures {
+    /// Whether the key may be forgotten
+    fn is_stale(&self, now: Instant, window: Duration) -> bool {
+        self.blocked_until <= now
+            && now.duration_since(self.last_failure) >= window
+    }
+}
+
+struct Keys<K> {
+    failures: HashMap<K, Failures>,
+    pruned_at: Instant,
+}
+
+/// Counts failed attempts per key, each failure past the free attempts
+/// doubles the time until the next attempt is allowed
+pub(crate) struct FailureCounter<K> {
+    policy: ThrottlePolicy,
+    /// Forgetting stale keys walks all of them, so it's only done this often
+    prune_interval: Duration,
+    keys: Mutex<Keys<K>>,
+}
+
+impl<K: Eq + Hash> FailureCounter<K> {
+    pub(crate) fn new(policy: ThrottlePolicy) -> Self {
+        Self {
+            policy,
+            prune_interval: Duration::from_secs(LOGIN_THROTTLE_PRUNE_INTERVAL),
+            keys: Mutex::new(Keys {
+                failures: HashMap::new(),
+                pruned_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub(crate) fn check(&self, key: &K) -> Result<(), Blocked> {
+        let keys = self.keys.lock().expect("failure counter poisoned");
+
+        let Some(entry) = keys.failures.get(key) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        if entry.blocked_until <= now {
+            return Ok(());
+        }
+
+        let retry_after = entry.blocked_until - now;
+        if entry.locked {
+            Err(Blocked::Locked(retry_after))
+        } else {
+            Err(Blocked::Throttled(retry_after))
+        }
+    }
+
+    pub(crate) fn record_failure(&self, key: K) {
+        let now = Instant::now();
+        let policy = self.policy;
+
+        let mut keys = self.keys.lock().expect("failure counter poisoned");
+
+        // Forget keys that stopped failing
+        if now.duration_since(keys.pruned_at) >= self.prune_interval {
+            keys.failures
+                .retain(|_, entry| !entry.is_stale(now, policy.window));
+            keys.pruned_at = now;
+        }
+
+        let entry = keys.failures.entry(key).or_insert(Failures {
+            count: 0,
+            last_failure: now,
+            blocked_until: now,
+            locked: false,
+        });
+
+        // A lockout that ran out, or failures outside the window, start over
+        // with a clean slate
+        let lockout_ended = entry.locked && entry.blocked_until <= now;
+        if lockout_ended || entry.is_stale(now, policy.window) {
+            entry.count = 0;
+            entry.locked = false;
+        }
+
+        entry.count += 1;
+        entry.last_failure = now;
+
+        match policy.lockout_threshold {
+            Some(threshold) if entry.count >= threshold => {
+                entry.locked = true;
+                entry.blocked_until = now + policy.lockout_duration;
+            }
+            _ if entry.count > policy.free_attempts => {
+                let exponent = (entry.count - policy.free_attempts - 1).min(31);
+                let delay = policy
+                    .backoff_base
+                    .saturating_mul(1 << exponent)
+                    .min(policy.backoff_max);
+                entry.blocked_until = now + delay;
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn reset(&self, key: &K) {
+        self.keys
+            .lock()
+            .expect("failure counter poisoned")
+            .failures
+            .remove(key);
+    }
+}
+
+/// Failed password logins, counted both for the account and the client
+pub(crate) struct LoginThrottle {
+    accounts: FailureCounter<String>,
+    ips: FailureCounter<IpAddr>,
+}
+
+impl LoginThrottle {
+    pub(crate) fn new() -> Self {
+        let window = Duration::from_secs(LOGIN_FAILURE_WINDOW);
+        let backoff_base = Duration::from_secs(LOGIN_BACKOFF_BASE);
+
+        Self {
+            accounts: FailureCounter::new(ThrottlePolicy {
+                free_attempts: ACCOUNT_FREE_LOGIN_ATTEMPTS,
+                backoff_base,
+                backoff_max: Duration::from_secs(ACCOUNT_LOGIN_BACKOFF_MAX),
+                lockout_threshold: Some(ACCOUNT_LOCKOUT_THRESHOLD),
+                lockout_duration: Duration::from_secs(ACCOUNT_LOCKOUT_DURATION),
+                window,
+            }),
+            ips: FailureCounter::new(ThrottlePolicy {
+                free_attempts: IP_FREE_LOGIN_ATTEMPTS,
+                backoff_base,
+                backoff_max: Duration::from_secs(IP_LOGIN_BACKOFF_MAX),
+                lockout_threshold: None,
+                lockout_duration: Duration::ZERO,
+                window,
+            }),
+        }
+    }
+
+    /// Accounts are keyed by the submitted email, so unknown emails are
+    /// throttled exactly like existing ones
+    fn account_key(email: &str) -> String {
+        email.trim().to_lowercase()
+    }
+
+    fn blocked_error(blocked: Blocked) -> UserError {
+        match blocked {
+            Blocked::Throttled(retry_after) => {
+                UserError::LoginThrottled(retry_after)
+            }
+            Blocked::Locked(retry_after) => {
+                UserError::AccountLocked(retry_after)
+            }
+        }
+    }
+
+    pub(crate) fn check(
+        &self,
+        email: &str,
+        ip: IpAddr,
+    ) -> Result<(), UserError> {
+        self.accounts
+            .check(&Self::account_key(email))
+            .and_then(|()| self.ips.check(&ip))
+            .map_err(Self::blocked_error)
+    }
+
+    pub(crate) fn record_failure(&self, email: &str, ip: IpAddr) {
+        self.accounts.record_failure(Self::account_key(email));
+        self.ips.record_failure(ip);
+    }
+
+    /// For reauthentication, which needs a valid session, so only the
+    /// account is counted. Shares its failures with the logins, guessing the
+    /// password here is no cheaper than at the login
+    pub(crate) fn check_account(&self, email: &str) -> Result<(), UserError> {
+        self.accounts
+            .check(&Self::account_key(email))
+            .map_err(Self::blocked_error)
+    }
+
+    pub(crate) fn record_account_failure(&self, email: &str) {
+        self.accounts.record_failure(Self::account_key(email));
+    }
+
+    /// Only the account is cleared, a valid login for one account shouldn't
+    /// reset the guesses made from the same client against others
+    pub(crate) fn record_success(&self, email: &str) {
+        self.accounts.reset(&Self::account_key(email));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: ThrottlePolicy = ThrottlePolicy {
+        free_attempts: 2,
+        backoff_base: Duration::from_secs(1),
+        backoff_max: Duration::from_secs(4),
+        lockout_threshold: Some(6),
+        lockout_duration: Duration::from_secs(60),
+        window: Duration::from_secs(60 * 60),
+    };
+
+    fn throttled_for(counter: &FailureCounter<&str>) -> Option<Duration> {
+        match counter.check(&"key") {
+            Ok(()) => None,
+            Err(Blocked::Throttled(retry_after)) => Some(retry_after),
+            Err(Blocked::Locked(_)) => panic!("unexpected lockout"),
+        }
+    }
+
+    fn assert_about(retry_after: Option<Duration>, secs: u64) {
+        let retry_after = retry_after.expect("not throttled");
+        let expected = Duration::from_secs(secs);
+        assert!(retry_after <= expected);
+        assert!(retry_after > expected - Duration::from_millis(500));
+    }
+
+    #[test]
+    fn backoff_doubles_after_the_free_attempts() {
+        let counter = FailureCounter::new(POLICY);
+
+        counter.record_failure("key");
+        counter.record_failure("key");
+        assert_eq!(throttled_for(&counter), None);
+
+        counter.record_failure("key");
+        assert_about(throttled_for(&counter), 1);
+
+        counter.record_failure("key");
+        assert_about(throttled_for(&counter), 2);
+
+        counter.record_failure("key");
+        assert_about(throttled_for(&counter), 4);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = ThrottlePolicy {
+            lockout_threshold: None,
+            ..POLICY
+        };
+        let counter = FailureCounter::new(policy);
+
+        for _ in 0..40 {
+            counter.record_failure("key");
+        }
+
+        assert_about(throttled_for(&counter), 4);
+    }
+
+    #[test]
+    fn locks_at_the_threshold() {
+        let counter = FailureCounter::new(POLICY);
+
+        for _ in 0..6 {
+            counter.record_failure("key");
+        }
+
+        let Err(Blocked::Locked(retry_after)) = counter.check(&"key") else {
+            panic!("not locked");
+        };
+        assert!(retry_after > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn failures_outside_the_window_start_over() {
+        let policy = ThrottlePolicy {
+            window: Duration::ZERO,
+            ..POLICY
+        };
+        let counter = FailureCounter::new(policy);
+
+        for _ in 0..3 {
+            counter.record_failure("key");
+        }
+
+        assert_eq!(throttled_for(&counter), None);
+    }
+
+    #[test]
+    fn reset_forgets_failures() {
+        let counter = FailureCounter::new(POLICY);
+
+        for _ in 0..3 {
+            counter.record_failure("key");
+        }
+        counter.reset(&"key");
+
+        assert_eq!(throttled_for(&counter), None);
+    }
+
+    #[test]
+    fn success_only_clears_the_account() {
+        let throttle = LoginThrottle::new();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+
+        for _ in 0..=ACCOUNT_FREE_LOGIN_ATTEMPTS {
+            throttle.record_failure("User@Example.com ", ip);
+        }
+        assert!(matches!(
+            throttle.check("user@example.com", ip),
+            Err(UserError::LoginThrottled(_))
+        ));
+
+        throttle.record_success("user@example.com");
+        assert!(throttle.check("USER@example.com", ip).is_ok());
+
+        for _ in 0..IP_FREE_LOGIN_ATTEMPTS {
+            throttle.record_failure("other@example.com", ip);
+        }
+        throttle.record_success("other@example.com");
+        assert!(matches!(
+            throttle.check("other@example.com", ip),
+            Err(UserError::LoginThrottled(_))
+        ));
+    }
+
+    #[test]
+    fn reauthentication_failures_count_for_the_account() {
+        let throttle = LoginThrottle::new();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+
+        for _ in 0..=ACCOUNT_FREE_LOGIN_ATTEMPTS {
+            throttle.record_account_failure("user@example.com");
+        }
+        assert!(matches!(
+            throttle.check_account("user@example.com"),
+            Err(UserError::LoginThrottled(_))
+        ));
+        assert!(matches!(
+            throttle.check("user@example.com", ip),
+            Err(UserError::LoginThrottled(_))
+        ));
+
+        // The client isn't counted, the session was valid
+        assert!(throttle.check("other@example.com", ip).is_ok());
+    }
+}
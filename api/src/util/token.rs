@@ -0,0 +1,36 @@
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use sha2::{Digest, Sha256};
+
+const TOKEN_LENGTH: usize = 48;
+const RECOVERY_CODE_CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// Generates a random url safe token
+pub(crate) fn generate_token() -> String {
+    OsRng
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Generates a code meant to be written down, without look-alike characters
+pub(crate) fn generate_recovery_code() -> String {
+    (0..RECOVERY_CODE_LENGTH)
+        .map(|_| {
+            let idx = OsRng.gen_range(0..RECOVERY_CODE_CHARSET.len());
+            char::from(RECOVERY_CODE_CHARSET[idx])
+        })
+        .collect()
+}
+
+/// Generates a 6-digit code, only safe combined with limited attempts
+pub(crate) fn generate_numeric_code() -> String {
+    format!("{:06}", OsRng.gen_range(0..1_000_000))
+}
+
+/// Hashes a token before it is stored, the tokens are random so a fast hash
+/// is sufficient
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
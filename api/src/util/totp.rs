@@ -0,0 +1,119 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::{rngs::OsRng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::{
+    config::constant::TOTP_ISSUER,
+    error::{ErrorRepr, ResultRepr},
+};
+
+const SECRET_LENGTH: usize = 20;
+const STEP: u64 = 30;
+/// Codes of the steps around the current one are accepted too, for clocks
+/// that are a bit off
+const SKEW: u64 = 1;
+
+/// Generates a base32 encoded secret
+pub(crate) fn generate_secret() -> String {
+    let mut secret = vec![0; SECRET_LENGTH];
+    OsRng.fill_bytes(&mut secret);
+
+    Secret::Raw(secret).to_encoded().to_string()
+}
+
+fn totp(secret: &str, account_name: String) -> ResultRepr<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(ErrorRepr::TotpSecret)?;
+
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        6,
+        // The skew is applied by `matching_step`
+        0,
+        STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name,
+    )?;
+
+    Ok(totp)
+}
+
+pub(crate) fn otpauth_uri(
+    secret: &str,
+    account_name: String,
+) -> ResultRepr<String> {
+    Ok(totp(secret, account_name)?.get_url())
+}
+
+/// The time step of the code when it is valid and newer than the last
+/// accepted step, so a code can't be replayed while it is still valid
+pub(crate) fn verify_code(
+    secret: &str,
+    code: &str,
+    last_step: Option<i64>,
+) -> ResultRepr<Option<i64>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    Ok(matching_step(
+        &totp(secret, String::new())?,
+        code,
+        now,
+        last_step,
+    ))
+}
+
+fn matching_step(
+    totp: &TOTP,
+    code: &str,
+    now: u64,
+    last_step: Option<i64>,
+) -> Option<i64> {
+    let current = now / STEP;
+
+    (current.saturating_sub(SKEW)..=current + SKEW)
+        .map(|step| step as i64)
+        .filter(|step| last_step.is_none_or(|last_step| *step > last_step))
+        .find(|step| totp.check(code, *step as u64 * STEP))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn codes() -> (TOTP, i64) {
+        let totp = totp(&generate_secret(), String::new()).unwrap();
+
+        (totp, (NOW / STEP) as i64)
+    }
+
+    #[test]
+    fn accepts_the_steps_around_now() {
+        let (totp, current) = codes();
+
+        for step in [current - 1, current, current + 1] {
+            let code = totp.generate(step as u64 * STEP);
+            assert_eq!(matching_step(&totp, &code, NOW, None), Some(step));
+        }
+
+        let code = totp.generate((current - 2) as u64 * STEP);
+        assert_eq!(matching_step(&totp, &code, NOW, None), None);
+    }
+
+    #[test]
+    fn rejects_replayed_steps() {
+        let (totp, current) = codes();
+        let code = totp.generate(current as u64 * STEP);
+
+        assert_eq!(matching_step(&totp, &code, NOW, Some(current)), None);
+        assert_eq!(matching_step(&totp, &code, NOW, Some(current + 1)), None);
+        assert_eq!(
+            matching_step(&totp, &code, NOW, Some(current - 1)),
+            Some(current)
+        );
+    }
+}
@@ -2,5 +2,13 @@
 
 pub mod prelude;
 
+pub mod one_time_token;
+pub mod passkey;
+pub mod permission;
+pub mod recovery_code;
 pub mod refresh_token;
+pub mod role;
+pub mod role_permission;
+pub mod trusted_device;
 pub mod user;
+pub mod user_role;
@@ -1,4 +1,12 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.7
 
+pub use super::one_time_token::Entity as OneTimeToken;
+pub use super::passkey::Entity as Passkey;
+pub use super::permission::Entity as Permission;
+pub use super::recovery_code::Entity as RecoveryCode;
 pub use super::refresh_token::Entity as RefreshToken;
+pub use super::role::Entity as Role;
+pub use super::role_permission::Entity as RolePermission;
+pub use super::trusted_device::Entity as TrustedDevice;
 pub use super::user::Entity as User;
+pub use super::user_role::Entity as UserRole;
@@ -17,12 +17,58 @@ pub struct Model {
     pub last_login: Option<TimeDateTime>,
     pub created_at: TimeDateTime,
     pub updated_at: TimeDateTime,
+    pub email_verified_at: Option<TimeDateTime>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<TimeDateTime>,
+    pub mfa_email_enabled: bool,
+    pub security_stamp: Uuid,
+    pub locale: String,
+    pub deleted_at: Option<TimeDateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub bio: Option<String>,
+    pub timezone: Option<String>,
+    pub website: Option<String>,
+    pub avatar_url: Option<String>,
+    pub status_changed_at: Option<TimeDateTime>,
+    pub status: String,
+    pub status_reason: Option<String>,
+    pub suspended_until: Option<TimeDateTime>,
+    pub totp_last_step: Option<i64>,
+    pub mfa_passkey_enabled: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::one_time_token::Entity")]
+    OneTimeToken,
+    #[sea_orm(has_many = "super::passkey::Entity")]
+    Passkey,
+    #[sea_orm(has_many = "super::recovery_code::Entity")]
+    RecoveryCode,
     #[sea_orm(has_many = "super::refresh_token::Entity")]
     RefreshToken,
+    #[sea_orm(has_many = "super::trusted_device::Entity")]
+    TrustedDevice,
+    #[sea_orm(has_many = "super::user_role::Entity")]
+    UserRole,
+}
+
+impl Related<super::one_time_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OneTimeToken.def()
+    }
+}
+
+impl Related<super::passkey::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Passkey.def()
+    }
+}
+
+impl Related<super::recovery_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCode.def()
+    }
 }
 
 impl Related<super::refresh_token::Entity> for Entity {
@@ -31,4 +77,25 @@ impl Related<super::refresh_token::Entity> for Entity {
     }
 }
 
+impl Related<super::trusted_device::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrustedDevice.def()
+    }
+}
+
+impl Related<super::user_role::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRole.def()
+    }
+}
+
+impl Related<super::role::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::user_role::Relation::Role.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::user_role::Relation::User.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,6 +2,22 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20230206_125352_create_user_table;
 mod m20230207_123520_create_refreshtoken_table;
+mod m20230315_094210_create_one_time_token_table;
+mod m20230322_141005_add_email_verified_at_to_user;
+mod m20230405_101230_add_totp_to_user;
+mod m20230405_101545_create_recovery_code_table;
+mod m20230419_083310_create_passkey_table;
+mod m20230503_152040_add_email_mfa;
+mod m20230517_090120_add_security_stamp_to_user;
+mod m20230517_091045_create_trusted_device_table;
+mod m20230531_101520_add_locale_to_user;
+mod m20230531_134210_add_deleted_at_to_user;
+mod m20230601_092730_add_profile_fields_to_user;
+mod m20230602_081415_add_admin_and_disabled_to_user;
+mod m20230605_101030_create_role_tables;
+mod m20230607_083025_add_status_to_user;
+mod m20230608_091520_add_totp_last_step_to_user;
+mod m20230609_084510_add_mfa_passkey_enabled_to_user;
 
 pub struct Migrator;
 
@@ -11,6 +27,26 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20230206_125352_create_user_table::Migration),
             Box::new(m20230207_123520_create_refreshtoken_table::Migration),
+            Box::new(m20230315_094210_create_one_time_token_table::Migration),
+            Box::new(m20230322_141005_add_email_verified_at_to_user::Migration),
+            Box::new(m20230405_101230_add_totp_to_user::Migration),
+            Box::new(m20230405_101545_create_recovery_code_table::Migration),
+            Box::new(m20230419_083310_create_passkey_table::Migration),
+            Box::new(m20230503_152040_add_email_mfa::Migration),
+            Box::new(m20230517_090120_add_security_stamp_to_user::Migration),
+            Box::new(m20230517_091045_create_trusted_device_table::Migration),
+            Box::new(m20230531_101520_add_locale_to_user::Migration),
+            Box::new(m20230531_134210_add_deleted_at_to_user::Migration),
+            Box::new(m20230601_092730_add_profile_fields_to_user::Migration),
+            Box::new(
+                m20230602_081415_add_admin_and_disabled_to_user::Migration,
+            ),
+            Box::new(m20230605_101030_create_role_tables::Migration),
+            Box::new(m20230607_083025_add_status_to_user::Migration),
+            Box::new(m20230608_091520_add_totp_last_step_to_user::Migration),
+            Box::new(
+                m20230609_084510_add_mfa_passkey_enabled_to_user::Migration,
+            ),
         ]
     }
 }
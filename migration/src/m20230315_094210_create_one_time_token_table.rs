@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+use entity::{one_time_token, user};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OneTimeToken::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OneTimeToken::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OneTimeToken::TokenHash)
+                            .string()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-one_time_token-token_hash")
+                            .col(one_time_token::Column::TokenHash),
+                    )
+                    .col(
+                        ColumnDef::new(OneTimeToken::UserUuid)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-one_time_token-user_uuid")
+                            .from(
+                                one_time_token::Entity,
+                                one_time_token::Column::UserUuid,
+                            )
+                            .to(user::Entity, user::Column::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(OneTimeToken::Purpose)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OneTimeToken::ExpiryDate)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OneTimeToken::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OneTimeToken::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum OneTimeToken {
+    Table,
+    Id,
+    TokenHash,
+    UserUuid,
+    Purpose,
+    ExpiryDate,
+    CreatedAt,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+use entity::{recovery_code, user};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCode::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecoveryCode::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RecoveryCode::UserUuid)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-recovery_code-user_uuid")
+                            .from(
+                                recovery_code::Entity,
+                                recovery_code::Column::UserUuid,
+                            )
+                            .to(user::Entity, user::Column::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(RecoveryCode::CodeHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-recovery_code-user_uuid-code_hash")
+                            .col(recovery_code::Column::UserUuid)
+                            .col(recovery_code::Column::CodeHash),
+                    )
+                    .col(
+                        ColumnDef::new(RecoveryCode::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCode::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum RecoveryCode {
+    Table,
+    Id,
+    UserUuid,
+    CodeHash,
+    CreatedAt,
+}
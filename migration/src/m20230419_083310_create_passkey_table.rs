@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+use entity::{passkey, user};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Passkey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Passkey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Passkey::UserUuid).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-passkey-user_uuid")
+                            .from(passkey::Entity, passkey::Column::UserUuid)
+                            .to(user::Entity, user::Column::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(Passkey::CredentialId)
+                            .string()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-passkey-credential_id")
+                            .col(passkey::Column::CredentialId),
+                    )
+                    .col(ColumnDef::new(Passkey::Name).string().not_null())
+                    .col(ColumnDef::new(Passkey::PublicKey).text().not_null())
+                    .col(
+                        ColumnDef::new(Passkey::SignCount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Passkey::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Passkey::LastUsedAt).timestamp())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Passkey::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Passkey {
+    Table,
+    Id,
+    UserUuid,
+    CredentialId,
+    Name,
+    PublicKey,
+    SignCount,
+    CreatedAt,
+    LastUsedAt,
+}
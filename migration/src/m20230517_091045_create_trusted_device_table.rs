@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use entity::{trusted_device, user};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TrustedDevice::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TrustedDevice::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TrustedDevice::Uuid)
+                            .uuid()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-trusted_device-uuid")
+                            .col(trusted_device::Column::Uuid),
+                    )
+                    .col(
+                        ColumnDef::new(TrustedDevice::UserUuid)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-trusted_device-user_uuid")
+                            .from(
+                                trusted_device::Entity,
+                                trusted_device::Column::UserUuid,
+                            )
+                            .to(user::Entity, user::Column::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(TrustedDevice::Name).string())
+                    .col(
+                        ColumnDef::new(TrustedDevice::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TrustedDevice::LastUsedAt).timestamp())
+                    .col(
+                        ColumnDef::new(TrustedDevice::ExpiryDate)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TrustedDevice::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TrustedDevice {
+    Table,
+    Id,
+    Uuid,
+    UserUuid,
+    Name,
+    CreatedAt,
+    LastUsedAt,
+    ExpiryDate,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Nullable, existing users simply have an empty profile
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::Bio).text())
+                    .add_column(ColumnDef::new(User::Timezone).string())
+                    .add_column(ColumnDef::new(User::Website).string())
+                    .add_column(ColumnDef::new(User::AvatarUrl).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Bio)
+                    .drop_column(User::Timezone)
+                    .drop_column(User::Website)
+                    .drop_column(User::AvatarUrl)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum User {
+    Table,
+    Bio,
+    Timezone,
+    Website,
+    AvatarUrl,
+}
@@ -0,0 +1,321 @@
+use sea_orm_migration::prelude::*;
+
+/// Permissions the api checks for, granted to the seeded admin role
+const PERMISSIONS: [(&str, &str); 2] = [
+    ("users.manage", "View, edit, disable and log out users"),
+    ("roles.manage", "Assign roles to users"),
+];
+
+const ADMIN_ROLE: &str = "admin";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Role::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Role::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Role::Name)
+                            .string()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Role::Description).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Permission::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Permission::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Permission::Name)
+                            .string()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Permission::Description).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePermission::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RolePermission::RoleId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RolePermission::PermissionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RolePermission::RoleId)
+                            .col(RolePermission::PermissionId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-role_permission-role_id")
+                            .from(RolePermission::Table, RolePermission::RoleId)
+                            .to(Role::Table, Role::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-role_permission-permission_id")
+                            .from(
+                                RolePermission::Table,
+                                RolePermission::PermissionId,
+                            )
+                            .to(Permission::Table, Permission::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRole::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserRole::UserUuid).uuid().not_null())
+                    .col(ColumnDef::new(UserRole::RoleId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(UserRole::UserUuid)
+                            .col(UserRole::RoleId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_role-user_uuid")
+                            .from(UserRole::Table, UserRole::UserUuid)
+                            .to(User::Table, User::Uuid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_role-role_id")
+                            .from(UserRole::Table, UserRole::RoleId)
+                            .to(Role::Table, Role::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the admin role with every permission
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Role::Table)
+                    .columns([Role::Name, Role::Description])
+                    .values_panic([
+                        ADMIN_ROLE.into(),
+                        "Manages users and their roles".into(),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut insert_permissions = Query::insert()
+            .into_table(Permission::Table)
+            .columns([Permission::Name, Permission::Description])
+            .to_owned();
+        for (name, description) in PERMISSIONS {
+            insert_permissions.values_panic([name.into(), description.into()]);
+        }
+        manager.exec_stmt(insert_permissions).await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(RolePermission::Table)
+                    .columns([
+                        RolePermission::RoleId,
+                        RolePermission::PermissionId,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .column((Role::Table, Role::Id))
+                            .column((Permission::Table, Permission::Id))
+                            .from(Role::Table)
+                            .from(Permission::Table)
+                            .and_where(
+                                Expr::col((Role::Table, Role::Name))
+                                    .eq(ADMIN_ROLE),
+                            )
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Custom(err.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        // `is_admin` becomes the admin role
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(UserRole::Table)
+                    .columns([UserRole::UserUuid, UserRole::RoleId])
+                    .select_from(
+                        Query::select()
+                            .column((User::Table, User::Uuid))
+                            .column((Role::Table, Role::Id))
+                            .from(User::Table)
+                            .from(Role::Table)
+                            .and_where(
+                                Expr::col((User::Table, User::IsAdmin))
+                                    .eq(true),
+                            )
+                            .and_where(
+                                Expr::col((Role::Table, Role::Name))
+                                    .eq(ADMIN_ROLE),
+                            )
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Custom(err.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::IsAdmin)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::IsAdmin)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Anyone with the admin role is an admin again
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(User::IsAdmin, true)
+                    .and_where(
+                        Expr::col(User::Uuid).in_subquery(
+                            Query::select()
+                                .column((UserRole::Table, UserRole::UserUuid))
+                                .from(UserRole::Table)
+                                .inner_join(
+                                    Role::Table,
+                                    Expr::col((Role::Table, Role::Id)).equals(
+                                        (UserRole::Table, UserRole::RoleId),
+                                    ),
+                                )
+                                .and_where(
+                                    Expr::col((Role::Table, Role::Name))
+                                        .eq(ADMIN_ROLE),
+                                )
+                                .to_owned(),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        for table in [
+            UserRole::Table.into_iden(),
+            RolePermission::Table.into_iden(),
+            Permission::Table.into_iden(),
+            Role::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Role {
+    Table,
+    Id,
+    Name,
+    Description,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Permission {
+    Table,
+    Id,
+    Name,
+    Description,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum RolePermission {
+    Table,
+    RoleId,
+    PermissionId,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum UserRole {
+    Table,
+    UserUuid,
+    RoleId,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum User {
+    Table,
+    Uuid,
+    IsAdmin,
+}
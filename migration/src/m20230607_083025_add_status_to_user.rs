@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .rename_column(User::DisabledAt, User::StatusChangedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::Status)
+                            .string()
+                            .not_null()
+                            .default("active"),
+                    )
+                    .add_column(ColumnDef::new(User::StatusReason).string())
+                    .add_column(
+                        ColumnDef::new(User::SuspendedUntil).timestamp(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only disabled users had the timestamp
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(User::Status, "disabled")
+                    .and_where(Expr::col(User::StatusChangedAt).is_not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Suspended and banned users end up disabled
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(User::StatusChangedAt, Option::<String>::None)
+                    .and_where(Expr::col(User::Status).eq("active"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::Status)
+                    .drop_column(User::StatusReason)
+                    .drop_column(User::SuspendedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .rename_column(User::StatusChangedAt, User::DisabledAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum User {
+    Table,
+    DisabledAt,
+    StatusChangedAt,
+    Status,
+    StatusReason,
+    SuspendedUntil,
+}